@@ -0,0 +1,186 @@
+use std::fmt;
+use std::str::FromStr;
+
+use blake2::Blake2bMac512;
+use hmac::Hmac;
+use sha2::digest::KeyInit;
+
+use crate::digester::SyncDigest;
+use crate::HashToolError;
+
+/// Hash algorithms supported by the tool.
+///
+/// MD5 and SHA-1 are kept only for verifying legacy checksums, they should not be used for
+/// anything security related.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    #[default]
+    Sha256,
+    Sha384,
+    Sha512,
+    Sha3_256,
+    Sha3_512,
+    Blake2b,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 9] = [
+        HashAlgorithm::Md5,
+        HashAlgorithm::Sha1,
+        HashAlgorithm::Sha256,
+        HashAlgorithm::Sha384,
+        HashAlgorithm::Sha512,
+        HashAlgorithm::Sha3_256,
+        HashAlgorithm::Sha3_512,
+        HashAlgorithm::Blake2b,
+        HashAlgorithm::Blake3,
+    ];
+
+    /// Name used on the command line, e.g. `sha3-256`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha384 => "sha384",
+            HashAlgorithm::Sha512 => "sha512",
+            HashAlgorithm::Sha3_256 => "sha3-256",
+            HashAlgorithm::Sha3_512 => "sha3-512",
+            HashAlgorithm::Blake2b => "blake2b",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    /// Digest size in bytes.
+    pub fn output_size(&self) -> usize {
+        match self {
+            HashAlgorithm::Md5 => 16,
+            HashAlgorithm::Sha1 => 20,
+            HashAlgorithm::Sha256 | HashAlgorithm::Sha3_256 | HashAlgorithm::Blake3 => 32,
+            HashAlgorithm::Sha384 => 48,
+            HashAlgorithm::Sha512 | HashAlgorithm::Sha3_512 | HashAlgorithm::Blake2b => 64,
+        }
+    }
+
+    pub(crate) fn new_hasher(&self) -> Box<dyn SyncDigest> {
+        match self {
+            HashAlgorithm::Md5 => Box::new(md5::Md5::default()),
+            HashAlgorithm::Sha1 => Box::new(sha1::Sha1::default()),
+            HashAlgorithm::Sha256 => Box::new(sha2::Sha256::default()),
+            HashAlgorithm::Sha384 => Box::new(sha2::Sha384::default()),
+            HashAlgorithm::Sha512 => Box::new(sha2::Sha512::default()),
+            HashAlgorithm::Sha3_256 => Box::new(sha3::Sha3_256::default()),
+            HashAlgorithm::Sha3_512 => Box::new(sha3::Sha3_512::default()),
+            HashAlgorithm::Blake2b => Box::new(blake2::Blake2b512::default()),
+            HashAlgorithm::Blake3 => Box::new(blake3::Hasher::new()),
+        }
+    }
+
+    /// Hasher for the keyed mode: HMAC for the MD and SHA families, the native keyed modes for
+    /// BLAKE2b (key of up to 64 bytes) and BLAKE3 (key of exactly 32 bytes).
+    pub(crate) fn new_keyed_hasher(
+        &self,
+        key: &[u8],
+    ) -> Result<Box<dyn SyncDigest>, HashToolError> {
+        let hasher: Box<dyn SyncDigest> = match self {
+            HashAlgorithm::Md5 => hmac::<Hmac<md5::Md5>>(key),
+            HashAlgorithm::Sha1 => hmac::<Hmac<sha1::Sha1>>(key),
+            HashAlgorithm::Sha256 => hmac::<Hmac<sha2::Sha256>>(key),
+            HashAlgorithm::Sha384 => hmac::<Hmac<sha2::Sha384>>(key),
+            HashAlgorithm::Sha512 => hmac::<Hmac<sha2::Sha512>>(key),
+            HashAlgorithm::Sha3_256 => hmac::<Hmac<sha3::Sha3_256>>(key),
+            HashAlgorithm::Sha3_512 => hmac::<Hmac<sha3::Sha3_512>>(key),
+            HashAlgorithm::Blake2b => match Blake2bMac512::new_from_slice(key) {
+                Ok(mac) => Box::new(mac),
+                Err(_e) => {
+                    return Err(HashToolError::InvalidKey(
+                        "BLAKE2b key must be at most 64 bytes!",
+                    ))
+                }
+            },
+            HashAlgorithm::Blake3 => match <[u8; 32]>::try_from(key) {
+                Ok(key) => Box::new(blake3::Hasher::new_keyed(&key)),
+                Err(_e) => {
+                    return Err(HashToolError::InvalidKey(
+                        "BLAKE3 key must be exactly 32 bytes!",
+                    ))
+                }
+            },
+        };
+
+        Ok(hasher)
+    }
+}
+
+fn hmac<M: KeyInit + SyncDigest + 'static>(key: &[u8]) -> Box<dyn SyncDigest> {
+    // HMAC accepts keys of any length
+    Box::new(M::new_from_slice(key).unwrap())
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = HashToolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.to_ascii_lowercase().replace('_', "-");
+
+        match normalized.as_str() {
+            "md5" => Ok(HashAlgorithm::Md5),
+            "sha1" | "sha-1" => Ok(HashAlgorithm::Sha1),
+            "sha256" | "sha-256" => Ok(HashAlgorithm::Sha256),
+            "sha384" | "sha-384" => Ok(HashAlgorithm::Sha384),
+            "sha512" | "sha-512" => Ok(HashAlgorithm::Sha512),
+            "sha3-256" => Ok(HashAlgorithm::Sha3_256),
+            "sha3-512" => Ok(HashAlgorithm::Sha3_512),
+            "blake2b" | "blake2b-512" => Ok(HashAlgorithm::Blake2b),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            _ => Err(HashToolError::UnknownAlgorithm(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::HashAlgorithm;
+
+    #[test]
+    fn parse_algorithm_names() {
+        for algorithm in HashAlgorithm::ALL {
+            assert_eq!(
+                algorithm.name().parse::<HashAlgorithm>().unwrap(),
+                algorithm
+            );
+        }
+
+        assert_eq!(
+            "SHA-256".parse::<HashAlgorithm>().unwrap(),
+            HashAlgorithm::Sha256
+        );
+        assert_eq!(
+            "sha3_256".parse::<HashAlgorithm>().unwrap(),
+            HashAlgorithm::Sha3_256
+        );
+        assert_eq!(
+            "crc32".parse::<HashAlgorithm>().unwrap_err().to_string(),
+            "unknown hash algorithm 'crc32'"
+        );
+    }
+
+    #[test]
+    fn output_size_matches_hasher() {
+        for algorithm in HashAlgorithm::ALL {
+            assert_eq!(
+                algorithm.new_hasher().output_size(),
+                algorithm.output_size()
+            );
+        }
+    }
+}
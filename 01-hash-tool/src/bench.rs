@@ -0,0 +1,181 @@
+use std::hint::black_box;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crate::progress::{format_bytes, throughput};
+use crate::{Digester, HashAlgorithm, Hasher};
+
+/// `hash-tool bench` settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchOptions {
+    /// Size of the in-memory buffer hashed over and over.
+    pub buffer_size: usize,
+    /// How long each algorithm runs.
+    pub duration: Duration,
+    pub algorithms: Vec<HashAlgorithm>,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        BenchOptions {
+            buffer_size: 16 * 1024 * 1024,
+            duration: Duration::from_secs(1),
+            algorithms: HashAlgorithm::ALL.to_vec(),
+        }
+    }
+}
+
+/// Byte count with an optional decimal `k`, `M` or `G` suffix, or a binary `Ki`, `Mi` or `Gi`
+/// one, e.g. `64M` or `64Mi`. Decimal like the units `format_bytes` prints.
+pub fn parse_size(text: &str) -> Option<usize> {
+    let digits = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, suffix) = text.split_at(digits);
+    let multiplier = match suffix {
+        "" => 1,
+        "k" | "K" => 1_000,
+        "M" => 1_000_000,
+        "G" => 1_000_000_000,
+        "Ki" => 1 << 10,
+        "Mi" => 1 << 20,
+        "Gi" => 1 << 30,
+        _ => return None,
+    };
+
+    number.parse::<usize>().ok()?.checked_mul(multiplier)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    pub algorithm: HashAlgorithm,
+    /// Bytes hashed within `elapsed`.
+    pub bytes: u64,
+    pub elapsed: Duration,
+}
+
+impl BenchResult {
+    /// Bytes per second.
+    pub fn throughput(&self) -> f64 {
+        throughput(self.bytes, self.elapsed)
+    }
+}
+
+/// Hashes the buffer with every algorithm until its time is up. Each algorithm gets at least one
+/// full pass, so tiny durations still measure something.
+pub fn run_bench(options: &BenchOptions) -> Vec<BenchResult> {
+    // anything but zeros, in case an implementation has a shortcut for them
+    let buffer: Vec<u8> = (0..options.buffer_size)
+        .map(|i| (i * 31 + 7) as u8)
+        .collect();
+
+    options
+        .algorithms
+        .iter()
+        .map(|&algorithm| {
+            let mut hasher = Hasher::new(algorithm);
+            let mut bytes = 0;
+            let started = Instant::now();
+
+            loop {
+                hasher.update(black_box(&buffer));
+                black_box(hasher.finalize());
+                bytes += buffer.len() as u64;
+
+                if started.elapsed() >= options.duration {
+                    break;
+                }
+            }
+
+            BenchResult {
+                algorithm,
+                bytes,
+                elapsed: started.elapsed(),
+            }
+        })
+        .collect()
+}
+
+/// Fastest algorithm first.
+pub fn write_table(out: &mut impl Write, results: &[BenchResult]) -> io::Result<()> {
+    let mut results = results.to_vec();
+    results.sort_by(|a, b| b.throughput().total_cmp(&a.throughput()));
+
+    writeln!(
+        out,
+        "{:<10} {:>12} {:>12}",
+        "algorithm", "throughput", "hashed"
+    )?;
+    for result in results {
+        writeln!(
+            out,
+            "{:<10} {:>12} {:>12}",
+            result.algorithm.to_string(),
+            format!("{}/s", format_bytes(result.throughput() as u64)),
+            format_bytes(result.bytes)
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::bench::{parse_size, run_bench, write_table, BenchOptions, BenchResult};
+    use crate::HashAlgorithm;
+
+    #[test]
+    fn parse_sizes() {
+        assert_eq!(parse_size("4096"), Some(4096));
+        assert_eq!(parse_size("4k"), Some(4000));
+        assert_eq!(parse_size("64M"), Some(64_000_000));
+        assert_eq!(parse_size("4Ki"), Some(4096));
+        assert_eq!(parse_size("64Mi"), Some(64 * 1024 * 1024));
+        assert_eq!(parse_size("M"), None);
+        assert_eq!(parse_size("64m"), None);
+        assert_eq!(parse_size("12x"), None);
+    }
+
+    #[test]
+    fn bench_every_algorithm() {
+        let options = BenchOptions {
+            buffer_size: 1024,
+            duration: Duration::from_millis(1),
+            ..BenchOptions::default()
+        };
+
+        let results = run_bench(&options);
+
+        assert_eq!(results.len(), HashAlgorithm::ALL.len());
+        assert!(results.iter().all(|result| result.bytes >= 1024));
+    }
+
+    #[test]
+    fn table_sorted_by_throughput() {
+        let second = Duration::from_secs(1);
+        let results = [
+            BenchResult {
+                algorithm: HashAlgorithm::Sha256,
+                bytes: 500_000_000,
+                elapsed: second,
+            },
+            BenchResult {
+                algorithm: HashAlgorithm::Blake3,
+                bytes: 2_000_000_000,
+                elapsed: second,
+            },
+        ];
+        let mut out = vec![];
+
+        write_table(&mut out, &results).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "algorithm    throughput       hashed\n\
+             blake3         2.0 GB/s       2.0 GB\n\
+             sha256       500.0 MB/s     500.0 MB\n"
+        );
+    }
+}
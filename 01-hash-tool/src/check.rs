@@ -0,0 +1,218 @@
+use crate::{hash_path, HashAlgorithm};
+
+/// Single line of a checksum file, as written by `sha256sum` (`<hex>  <path>`) or by BSD tools
+/// and `sha256sum --tag` (`SHA256 (<path>) = <hex>`).
+#[derive(Debug, PartialEq)]
+pub struct ChecksumEntry {
+    pub path: String,
+    pub digest: Vec<u8>,
+    /// Only BSD lines name the algorithm, GNU lines use the one picked on the command line.
+    pub algorithm: Option<HashAlgorithm>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CheckStatus {
+    Ok,
+    Failed,
+    ReadFailed,
+}
+
+/// Parses one line of a checksum file, returns `None` for lines which are not properly
+/// formatted, comments included.
+pub fn parse_checksum_line(line: &str) -> Option<ChecksumEntry> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    parse_bsd_line(line).or_else(|| parse_gnu_line(line))
+}
+
+fn parse_gnu_line(line: &str) -> Option<ChecksumEntry> {
+    // coreutils prefixes the line with a backslash when the file name had to be escaped
+    let (line, escaped) = match line.strip_prefix('\\') {
+        Some(rest) => (rest, true),
+        None => (line, false),
+    };
+
+    let (digest, rest) = line.split_once(' ')?;
+    // second separator char is ' ' for text mode and '*' for binary mode
+    let path = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*'))?;
+    let digest = hex::decode(digest).ok()?;
+
+    if path.is_empty() || digest.is_empty() {
+        return None;
+    }
+
+    let path = if escaped {
+        unescape_path(path)?
+    } else {
+        path.to_string()
+    };
+
+    Some(ChecksumEntry {
+        path,
+        digest,
+        algorithm: None,
+    })
+}
+
+fn parse_bsd_line(line: &str) -> Option<ChecksumEntry> {
+    let (tag, rest) = line.split_once(" (")?;
+    let (path, digest) = rest.rsplit_once(") = ")?;
+    let algorithm: HashAlgorithm = tag.parse().ok()?;
+    let digest = hex::decode(digest).ok()?;
+
+    if path.is_empty() || digest.len() != algorithm.output_size() {
+        return None;
+    }
+
+    Some(ChecksumEntry {
+        path: path.to_string(),
+        digest,
+        algorithm: Some(algorithm),
+    })
+}
+
+fn unescape_path(path: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(path.len());
+    let mut chars = path.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next()? {
+            '\\' => unescaped.push('\\'),
+            'n' => unescaped.push('\n'),
+            'r' => unescaped.push('\r'),
+            _ => return None,
+        }
+    }
+
+    Some(unescaped)
+}
+
+/// Formats a line of a `sha256sum` compatible manifest, escaping file names the way coreutils
+/// does so that `parse_checksum_line` reads them back.
+pub fn format_checksum_line(digest: &str, path: &str) -> String {
+    if path.contains(['\\', '\n', '\r']) {
+        let escaped = path
+            .replace('\\', "\\\\")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r");
+        format!("\\{digest}  {escaped}")
+    } else {
+        format!("{digest}  {path}")
+    }
+}
+
+/// Re-hashes the file referenced by `entry` and compares it with the recorded digest.
+pub fn verify_entry(
+    entry: &ChecksumEntry,
+    default_algorithm: HashAlgorithm,
+    key: Option<&[u8]>,
+) -> CheckStatus {
+    let algorithm = entry.algorithm.unwrap_or(default_algorithm);
+
+    match hash_path(&entry.path, algorithm, key) {
+        Ok(hash) if hash.digest == entry.digest => CheckStatus::Ok,
+        Ok(_hash) => CheckStatus::Failed,
+        Err(_e) => CheckStatus::ReadFailed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::check::{
+        format_checksum_line, parse_checksum_line, verify_entry, CheckStatus, ChecksumEntry,
+    };
+    use crate::HashAlgorithm;
+
+    const TEST_FILE_SHA256: &str =
+        "cd6c8edf218a2c38d46d5b0ecf235a71185dc11f57735f607cdcc5e5339b2c3c";
+
+    #[test]
+    fn parse_gnu_line() {
+        let text_mode = parse_checksum_line(&format!("{TEST_FILE_SHA256}  test-file-1.txt"));
+        let binary_mode = parse_checksum_line(&format!("{TEST_FILE_SHA256} *test-file-1.txt"));
+
+        let expected = ChecksumEntry {
+            path: String::from("test-file-1.txt"),
+            digest: hex::decode(TEST_FILE_SHA256).unwrap(),
+            algorithm: None,
+        };
+        assert_eq!(text_mode, Some(expected));
+        assert_eq!(binary_mode.unwrap().path, "test-file-1.txt");
+    }
+
+    #[test]
+    fn parse_gnu_line_with_spaces_and_escapes() {
+        let spaces = parse_checksum_line("abcd  dir/my file.txt").unwrap();
+        let escaped = parse_checksum_line("\\abcd  new\\nline\\\\name").unwrap();
+
+        assert_eq!(spaces.path, "dir/my file.txt");
+        assert_eq!(escaped.path, "new\nline\\name");
+    }
+
+    #[test]
+    fn parse_bsd_line() {
+        let entry = parse_checksum_line(&format!("SHA256 (test (1).txt) = {TEST_FILE_SHA256}"));
+
+        assert_eq!(
+            entry,
+            Some(ChecksumEntry {
+                path: String::from("test (1).txt"),
+                digest: hex::decode(TEST_FILE_SHA256).unwrap(),
+                algorithm: Some(HashAlgorithm::Sha256),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_improperly_formatted_lines() {
+        assert_eq!(parse_checksum_line(""), None);
+        assert_eq!(parse_checksum_line("# comment"), None);
+        assert_eq!(parse_checksum_line("not-hex  file.txt"), None);
+        assert_eq!(parse_checksum_line(TEST_FILE_SHA256), None);
+        assert_eq!(parse_checksum_line("SHA256 (file.txt) = abcd"), None);
+    }
+
+    #[test]
+    fn format_line_round_trip() {
+        for path in ["dir/test-file-1.txt", "new\nline\\name"] {
+            let line = format_checksum_line(TEST_FILE_SHA256, path);
+            let entry = parse_checksum_line(&line).unwrap();
+
+            assert_eq!(entry.path, path);
+            assert_eq!(entry.digest, hex::decode(TEST_FILE_SHA256).unwrap());
+        }
+        assert_eq!(
+            format_checksum_line(TEST_FILE_SHA256, "test-file-1.txt"),
+            format!("{TEST_FILE_SHA256}  test-file-1.txt")
+        );
+    }
+
+    #[test]
+    fn verify_entries() {
+        let mut entry =
+            parse_checksum_line(&format!("{TEST_FILE_SHA256}  test-file-1.txt")).unwrap();
+        assert_eq!(
+            verify_entry(&entry, HashAlgorithm::Sha256, None),
+            CheckStatus::Ok
+        );
+        assert_eq!(
+            verify_entry(&entry, HashAlgorithm::Sha3_256, None),
+            CheckStatus::Failed
+        );
+
+        entry.path = String::from("non-existent");
+        assert_eq!(
+            verify_entry(&entry, HashAlgorithm::Sha256, None),
+            CheckStatus::ReadFailed
+        );
+    }
+}
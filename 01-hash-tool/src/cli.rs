@@ -0,0 +1,587 @@
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+
+use crate::bench::{parse_size, BenchOptions};
+use crate::dupes::DupesOptions;
+use crate::encoding::DigestEncoding;
+use crate::fingerprint::Fingerprint;
+use crate::manifest::{DiffOptions, SnapshotOptions};
+use crate::normalize::TextNormalization;
+use crate::output::OutputFormat;
+use crate::password::{PasswordAlgorithm, PasswordCommand, PasswordParams};
+use crate::walk::WalkOptions;
+use crate::{Command, Config, HashAlgorithm, HashToolError, KeySource};
+
+const ALGORITHM_HELP: &str =
+    "Hash algorithm: md5, sha1, sha256, sha384, sha512, sha3-256, sha3-512, blake2b or blake3";
+const JOBS_HELP: &str = "Number of files hashed concurrently, 0 means one per core";
+
+/// Hash, verify and compare files.
+#[derive(Debug, Parser)]
+#[command(
+    name = "hash-tool",
+    bin_name = "hash-tool",
+    version,
+    arg_required_else_help = true
+)]
+#[command(after_help = "Arguments without a subcommand are those of `hash-tool hash`.")]
+struct Cli {
+    #[command(subcommand)]
+    command: CliCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum CliCommand {
+    /// Print the digest of every file, the default without a subcommand
+    Hash(HashArgs),
+    /// Verify the files listed in a checksum file, like `sha256sum --check`
+    Check(CheckArgs),
+    /// Hash or verify a password read from the standard input
+    Password(PasswordArgs),
+    /// Measure the throughput of every algorithm
+    Bench(BenchArgs),
+    /// Group files with identical content
+    Dupes(DupesArgs),
+    /// Write a manifest of every file in a directory
+    Snapshot(SnapshotArgs),
+    /// Compare a directory against a manifest written by `snapshot`
+    Diff(DiffArgs),
+    /// Print the completion script for a shell
+    Completions { shell: Shell },
+}
+
+/// Parses a whole command line, program name included.
+pub(crate) fn parse(args: impl Iterator<Item = String>) -> Result<Command, HashToolError> {
+    let mut args: Vec<String> = args.collect();
+
+    // `hash-tool file.txt` is short for `hash-tool hash file.txt`
+    let implicit_hash = match args.get(1).map(String::as_str) {
+        None | Some("-h" | "--help" | "-V" | "--version" | "help") => false,
+        Some(arg) => Cli::command().find_subcommand(arg).is_none(),
+    };
+    if implicit_hash {
+        args.insert(1, String::from("hash"));
+    }
+
+    let command = match Cli::try_parse_from(args)
+        .map_err(HashToolError::Cli)?
+        .command
+    {
+        CliCommand::Hash(args) => Command::Hash(args.into_config()?),
+        CliCommand::Check(args) => Command::Hash(args.into_config()),
+        CliCommand::Password(args) => Command::Password(args.into_command()?),
+        CliCommand::Bench(args) => Command::Bench(args.into_options()),
+        CliCommand::Dupes(args) => Command::Dupes(args.into_options()),
+        CliCommand::Snapshot(args) => Command::Snapshot(args.into_options()),
+        CliCommand::Diff(args) => Command::Diff(args.into_options()),
+        CliCommand::Completions { shell } => Command::Completions(shell),
+    };
+
+    Ok(command)
+}
+
+/// Writes the completion script of `hash-tool` for `shell`.
+pub fn write_completions(shell: Shell, out: &mut dyn Write) {
+    clap_complete::generate(shell, &mut Cli::command(), "hash-tool", out);
+}
+
+#[derive(Debug, Args)]
+struct WalkArgs {
+    /// Follow symlinks found in directories
+    #[arg(short = 'L', long)]
+    follow_symlinks: bool,
+    /// Skip paths matching a glob pattern, can be repeated
+    #[arg(long, value_name = "PATTERN")]
+    exclude: Vec<String>,
+}
+
+impl WalkArgs {
+    fn into_options(self, recursive: bool) -> WalkOptions {
+        WalkOptions {
+            recursive,
+            follow_symlinks: self.follow_symlinks,
+            exclude: self.exclude,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+#[group(multiple = false)]
+struct KeyArgs {
+    /// Keyed hashing (HMAC, or the keyed mode of BLAKE2b and BLAKE3) with this key
+    #[arg(long, value_name = "KEY")]
+    hmac_key: Option<String>,
+    /// Keyed hashing with the raw content of a file as the key
+    #[arg(long, value_name = "FILE")]
+    key_file: Option<String>,
+}
+
+impl KeyArgs {
+    fn into_source(self) -> Option<KeySource> {
+        match (self.hmac_key, self.key_file) {
+            (Some(key), _) => Some(KeySource::Inline(key)),
+            (None, Some(path)) => Some(KeySource::File(path)),
+            (None, None) => None,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+#[command(name = "hash-tool")]
+pub(crate) struct HashArgs {
+    /// Files or directories, `-` reads the standard input
+    #[arg(value_name = "PATH", required_unless_present = "check")]
+    paths: Vec<String>,
+    #[arg(short, long, help = ALGORITHM_HELP, default_value_t = HashAlgorithm::default())]
+    algorithm: HashAlgorithm,
+    /// Verify the files listed in a checksum file instead, like the `check` subcommand
+    #[arg(short, long, value_name = "FILE")]
+    check: Option<String>,
+    /// Descend into directories
+    #[arg(short, long)]
+    recursive: bool,
+    #[command(flatten)]
+    walk: WalkArgs,
+    /// Don't follow symlinks found in directories, the default
+    #[arg(long, overrides_with = "follow_symlinks")]
+    no_follow_symlinks: bool,
+    #[arg(short, long, help = JOBS_HELP, default_value_t = 0)]
+    jobs: usize,
+    #[command(flatten)]
+    key: KeyArgs,
+    /// Output format: text, json or bare
+    #[arg(short, long, value_name = "FORMAT")]
+    format: Option<OutputFormat>,
+    /// Digest encoding: hex, HEX, base64, base64url, base32, multihash or sri
+    #[arg(short, long, value_name = "ENCODING")]
+    encoding: Option<DigestEncoding>,
+    /// Digest the single file must match, in any supported encoding
+    #[arg(long, value_name = "DIGEST", conflicts_with = "merkle")]
+    expect: Option<String>,
+    /// Digest files as the Merkle root over fixed-size chunks
+    #[arg(long)]
+    merkle: bool,
+    /// Chunk size for --merkle, 1 MiB by default
+    #[arg(long, value_name = "BYTES", requires = "merkle")]
+    chunk_size: Option<NonZeroUsize>,
+    /// Write the chunk digests of the single file for --merkle
+    #[arg(long, value_name = "FILE", requires = "merkle")]
+    chunk_manifest: Option<String>,
+    /// Print the git blob id (git, git-sha256) or the CIDv1 (cid) instead of a digest. The kind
+    /// picks the algorithm and the encoding, so neither can be given with it
+    #[arg(
+        long,
+        value_name = "KIND",
+        conflicts_with_all = ["merkle", "hmac_key", "key_file", "expect", "algorithm", "encoding"]
+    )]
+    fingerprint: Option<Fingerprint>,
+    /// Never draw the progress bar, which is otherwise shown when stdout is a terminal
+    #[arg(long)]
+    no_progress: bool,
+    /// Report size, time and throughput of every file on stderr
+    #[arg(long)]
+    stats: bool,
+    /// Normalize text before hashing, a comma separated list of crlf, trailing-whitespace, bom,
+    /// nfc and final-newline, or all
+    #[arg(long, value_name = "LIST", conflicts_with_all = ["check", "merkle", "fingerprint"])]
+    text: Option<TextNormalization>,
+}
+
+impl HashArgs {
+    pub(crate) fn into_config(self) -> Result<Config, HashToolError> {
+        if self.expect.is_some() && self.paths.len() != 1 {
+            let message = "--expect needs exactly one file path";
+            let error = HashArgs::command().error(ErrorKind::WrongNumberOfValues, message);
+            return Err(HashToolError::Cli(error));
+        }
+
+        Ok(Config {
+            file_paths: self.paths,
+            algorithm: self.algorithm,
+            check_file: self.check,
+            walk: self.walk.into_options(self.recursive),
+            jobs: self.jobs,
+            key: self.key.into_source(),
+            format: self.format.unwrap_or_default(),
+            encoding: self.encoding.unwrap_or_default(),
+            expect: self.expect,
+            merkle: self.merkle,
+            chunk_size: self.chunk_size.map_or(0, NonZeroUsize::get),
+            chunk_manifest: self.chunk_manifest,
+            fingerprint: self.fingerprint,
+            no_progress: self.no_progress,
+            stats: self.stats,
+            text: self.text.unwrap_or_default(),
+        })
+    }
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct CheckArgs {
+    /// Checksum file in the `sha256sum` format, `-` reads the standard input
+    #[arg(value_name = "FILE")]
+    check_file: String,
+    #[arg(short, long, help = ALGORITHM_HELP, default_value_t = HashAlgorithm::default())]
+    algorithm: HashAlgorithm,
+    #[command(flatten)]
+    key: KeyArgs,
+}
+
+impl CheckArgs {
+    fn into_config(self) -> Config {
+        Config {
+            check_file: Some(self.check_file),
+            algorithm: self.algorithm,
+            key: self.key.into_source(),
+            ..Config::default()
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct PasswordArgs {
+    #[command(subcommand)]
+    command: PasswordSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum PasswordSubcommand {
+    /// Hash the password with a random salt
+    Hash {
+        /// argon2id, bcrypt, scrypt or pbkdf2
+        #[arg(short, long, default_value = "argon2id")]
+        algorithm: PasswordAlgorithm,
+        /// Cost parameters by their PHC names, e.g. `m=65536,t=3,p=4` for argon2id
+        #[arg(long, value_name = "PARAMS")]
+        cost: Option<String>,
+    },
+    /// Check the password against a hash, exits with 1 when it doesn't match
+    Verify {
+        /// PHC string, or a bcrypt hash
+        hash: String,
+    },
+}
+
+impl PasswordArgs {
+    pub(crate) fn into_command(self) -> Result<PasswordCommand, HashToolError> {
+        match self.command {
+            PasswordSubcommand::Hash { algorithm, cost } => {
+                let params = PasswordParams::parse(algorithm, &cost.unwrap_or_default())?;
+                Ok(PasswordCommand::Hash(params))
+            }
+            PasswordSubcommand::Verify { hash } => Ok(PasswordCommand::Verify(hash)),
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct BenchArgs {
+    /// Algorithm to measure, can be repeated, all of them by default
+    #[arg(short, long)]
+    algorithm: Vec<HashAlgorithm>,
+    /// Size of the buffer hashed over and over, e.g. 64M (decimal) or 64Mi (binary), 16Mi by
+    /// default
+    #[arg(long, value_name = "BYTES", value_parser = parse_buffer_size)]
+    size: Option<usize>,
+    /// Seconds each algorithm runs, 1 by default
+    #[arg(long, value_name = "SECS", value_parser = parse_duration)]
+    duration: Option<Duration>,
+}
+
+impl BenchArgs {
+    pub(crate) fn into_options(self) -> BenchOptions {
+        let defaults = BenchOptions::default();
+
+        BenchOptions {
+            buffer_size: self.size.unwrap_or(defaults.buffer_size),
+            duration: self.duration.unwrap_or(defaults.duration),
+            algorithms: match self.algorithm.is_empty() {
+                true => defaults.algorithms,
+                false => self.algorithm,
+            },
+        }
+    }
+}
+
+fn parse_buffer_size(text: &str) -> Result<usize, String> {
+    match parse_size(text) {
+        Some(size) if size > 0 => Ok(size),
+        _ => Err(String::from("expected a byte count like 4096, 64M or 64Mi")),
+    }
+}
+
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    match text.parse::<f64>().map(Duration::try_from_secs_f64) {
+        Ok(Ok(duration)) if !duration.is_zero() => Ok(duration),
+        _ => Err(String::from("expected a positive number of seconds")),
+    }
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct DupesArgs {
+    /// Directories or files to search, directories are walked recursively
+    #[arg(value_name = "PATH", required = true)]
+    paths: Vec<String>,
+    #[arg(short, long, help = ALGORITHM_HELP, default_value_t = HashAlgorithm::default())]
+    algorithm: HashAlgorithm,
+    #[command(flatten)]
+    walk: WalkArgs,
+    #[arg(short, long, help = JOBS_HELP, default_value_t = 0)]
+    jobs: usize,
+    /// Output format: text or json
+    #[arg(short, long, value_name = "FORMAT")]
+    format: Option<OutputFormat>,
+}
+
+impl DupesArgs {
+    pub(crate) fn into_options(self) -> DupesOptions {
+        DupesOptions {
+            paths: self.paths,
+            algorithm: self.algorithm,
+            walk: self.walk.into_options(true),
+            jobs: self.jobs,
+            format: self.format.unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct SnapshotArgs {
+    /// Directory to walk
+    dir: String,
+    /// Where to write the manifest instead of the standard output
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<String>,
+    #[arg(short, long, help = ALGORITHM_HELP, default_value_t = HashAlgorithm::default())]
+    algorithm: HashAlgorithm,
+    #[command(flatten)]
+    walk: WalkArgs,
+    #[arg(short, long, help = JOBS_HELP, default_value_t = 0)]
+    jobs: usize,
+}
+
+impl SnapshotArgs {
+    pub(crate) fn into_options(self) -> SnapshotOptions {
+        SnapshotOptions {
+            dir: self.dir,
+            output: self.output,
+            algorithm: self.algorithm,
+            walk: self.walk.into_options(false),
+            jobs: self.jobs,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct DiffArgs {
+    /// Manifest written by `snapshot`, its algorithm is used for the directory
+    manifest: String,
+    /// Directory to compare
+    dir: String,
+    #[command(flatten)]
+    walk: WalkArgs,
+    #[arg(short, long, help = JOBS_HELP, default_value_t = 0)]
+    jobs: usize,
+    /// Output format: text or json
+    #[arg(short, long, value_name = "FORMAT")]
+    format: Option<OutputFormat>,
+}
+
+impl DiffArgs {
+    pub(crate) fn into_options(self) -> DiffOptions {
+        DiffOptions {
+            manifest: self.manifest,
+            dir: self.dir,
+            walk: self.walk.into_options(false),
+            jobs: self.jobs,
+            format: self.format.unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+    use clap_complete::Shell;
+
+    use std::time::Duration;
+
+    use crate::bench::BenchOptions;
+    use crate::cli::{parse, write_completions, Cli};
+    use crate::dupes::DupesOptions;
+    use crate::manifest::DiffOptions;
+    use crate::output::OutputFormat;
+    use crate::password::{PasswordAlgorithm, PasswordCommand, PasswordParams};
+    use crate::walk::WalkOptions;
+    use crate::{Command, Config, HashAlgorithm, HashToolError, EXIT_USAGE};
+
+    fn build(args: &[&str]) -> Result<Command, HashToolError> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn command_line_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn hash_without_subcommand() {
+        let expected = Config {
+            file_paths: vec![String::from("file.txt")],
+            algorithm: HashAlgorithm::Md5,
+            ..Config::default()
+        };
+
+        assert_eq!(
+            build(&["hash-tool", "-a", "md5", "file.txt"]).unwrap(),
+            Command::Hash(expected)
+        );
+        assert_eq!(
+            build(&["hash-tool", "-a", "md5", "file.txt"]).unwrap(),
+            build(&["hash-tool", "hash", "-a", "md5", "file.txt"]).unwrap()
+        );
+
+        let args = ["program-name", "-L", "--no-follow-symlinks", "file.txt"].map(String::from);
+        assert!(
+            !Config::build(args.into_iter())
+                .unwrap()
+                .walk
+                .follow_symlinks
+        );
+    }
+
+    #[test]
+    fn check_subcommand() {
+        assert_eq!(
+            build(&["hash-tool", "check", "SHA256SUMS"]).unwrap(),
+            Command::Hash(Config {
+                check_file: Some(String::from("SHA256SUMS")),
+                ..Config::default()
+            })
+        );
+        assert_eq!(
+            build(&["hash-tool", "password", "verify", "$2b$04$abc"]).unwrap(),
+            Command::Password(PasswordCommand::Verify(String::from("$2b$04$abc")))
+        );
+    }
+
+    #[test]
+    fn password_subcommand() {
+        let args = [
+            "hash-tool",
+            "password",
+            "hash",
+            "-a",
+            "scrypt",
+            "--cost",
+            "ln=15,p=2",
+        ];
+
+        assert_eq!(
+            build(&args).unwrap(),
+            Command::Password(PasswordCommand::Hash(PasswordParams::Scrypt {
+                log_n: 15,
+                r: 8,
+                p: 2
+            }))
+        );
+        assert_eq!(
+            build(&["hash-tool", "password", "hash"]).unwrap(),
+            Command::Password(PasswordCommand::Hash(PasswordParams::recommended(
+                PasswordAlgorithm::Argon2id
+            )))
+        );
+        assert!(build(&["hash-tool", "password", "verify"]).is_err());
+        assert!(build(&["hash-tool", "password", "hash", "--cost"]).is_err());
+        assert!(build(&["hash-tool", "password"]).is_err());
+    }
+
+    #[test]
+    fn bench_subcommand() {
+        let args = [
+            "hash-tool",
+            "bench",
+            "--size",
+            "4Ki",
+            "--duration",
+            "0.5",
+            "-a",
+            "blake3",
+        ];
+
+        assert_eq!(
+            build(&args).unwrap(),
+            Command::Bench(BenchOptions {
+                buffer_size: 4096,
+                duration: Duration::from_millis(500),
+                algorithms: vec![HashAlgorithm::Blake3],
+            })
+        );
+        assert!(build(&["hash-tool", "bench", "--duration", "1e300"]).is_err());
+        assert!(build(&["hash-tool", "bench", "--size", "12x"]).is_err());
+    }
+
+    #[test]
+    fn dupes_subcommand() {
+        assert_eq!(
+            build(&["hash-tool", "dupes", "dir", "-j", "2"]).unwrap(),
+            Command::Dupes(DupesOptions {
+                paths: vec![String::from("dir")],
+                algorithm: HashAlgorithm::default(),
+                walk: WalkOptions {
+                    recursive: true,
+                    ..WalkOptions::default()
+                },
+                jobs: 2,
+                format: OutputFormat::Text,
+            })
+        );
+        assert!(build(&["hash-tool", "dupes"]).is_err());
+    }
+
+    #[test]
+    fn diff_subcommand() {
+        assert_eq!(
+            build(&["hash-tool", "diff", "manifest.json", "dir", "-f", "json"]).unwrap(),
+            Command::Diff(DiffOptions {
+                manifest: String::from("manifest.json"),
+                dir: String::from("dir"),
+                walk: WalkOptions::default(),
+                jobs: 0,
+                format: OutputFormat::Json,
+            })
+        );
+        assert!(build(&["hash-tool", "diff", "manifest.json"]).is_err());
+    }
+
+    #[test]
+    fn help_and_version_exit_successfully() {
+        for args in [
+            &["hash-tool", "--help"][..],
+            &["hash-tool", "--version"],
+            &["hash-tool", "dupes", "--help"],
+            &["hash-tool", "help", "snapshot"],
+        ] {
+            let error = build(args).unwrap_err();
+            assert_eq!(error.exit_code(), 0, "{args:?}");
+        }
+        assert_eq!(build(&["hash-tool"]).unwrap_err().exit_code(), EXIT_USAGE);
+    }
+
+    #[test]
+    fn completion_script() {
+        let mut script = vec![];
+
+        write_completions(Shell::Bash, &mut script);
+
+        let script = String::from_utf8(script).unwrap();
+        assert!(script.contains("hash-tool"));
+        assert!(script.contains("--fingerprint"));
+        assert!(script.contains("snapshot"));
+    }
+}
@@ -0,0 +1,299 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use sha2::digest::DynDigest;
+
+use crate::{HashAlgorithm, HashToolError};
+
+/// Incremental hash function, so callers don't have to depend on the individual digest crates.
+pub trait Digester {
+    fn update(&mut self, data: &[u8]);
+
+    /// Returns the digest of everything since the last reset and starts over.
+    fn finalize(&mut self) -> Vec<u8>;
+
+    /// Drops everything fed so far.
+    fn reset(&mut self);
+
+    fn output_size(&self) -> usize;
+}
+
+impl<D: Digester + ?Sized> Digester for &mut D {
+    fn update(&mut self, data: &[u8]) {
+        (**self).update(data)
+    }
+
+    fn finalize(&mut self) -> Vec<u8> {
+        (**self).finalize()
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+
+    fn output_size(&self) -> usize {
+        (**self).output_size()
+    }
+}
+
+/// `DynDigest` which can be moved to and shared with other threads, also once cloned.
+pub(crate) trait SyncDigest: DynDigest + Send + Sync {
+    fn clone_sync(&self) -> Box<dyn SyncDigest>;
+}
+
+impl<D: DynDigest + Clone + Send + Sync + 'static> SyncDigest for D {
+    fn clone_sync(&self) -> Box<dyn SyncDigest> {
+        Box::new(self.clone())
+    }
+}
+
+/// `Digester` for any of the supported algorithms, plain or keyed. It's `Send` and `Sync`, so it
+/// can be handed to other threads.
+pub struct Hasher {
+    algorithm: HashAlgorithm,
+    inner: Box<dyn SyncDigest>,
+}
+
+impl Hasher {
+    pub fn new(algorithm: HashAlgorithm) -> Hasher {
+        Hasher {
+            algorithm,
+            inner: algorithm.new_hasher(),
+        }
+    }
+
+    /// HMAC for the SHA families and MD5, the native keyed modes of BLAKE2b and BLAKE3.
+    pub fn keyed(algorithm: HashAlgorithm, key: &[u8]) -> Result<Hasher, HashToolError> {
+        Ok(Hasher {
+            algorithm,
+            inner: algorithm.new_keyed_hasher(key)?,
+        })
+    }
+
+    pub fn md5() -> Hasher {
+        Hasher::new(HashAlgorithm::Md5)
+    }
+
+    pub fn sha1() -> Hasher {
+        Hasher::new(HashAlgorithm::Sha1)
+    }
+
+    pub fn sha256() -> Hasher {
+        Hasher::new(HashAlgorithm::Sha256)
+    }
+
+    pub fn sha384() -> Hasher {
+        Hasher::new(HashAlgorithm::Sha384)
+    }
+
+    pub fn sha512() -> Hasher {
+        Hasher::new(HashAlgorithm::Sha512)
+    }
+
+    pub fn sha3_256() -> Hasher {
+        Hasher::new(HashAlgorithm::Sha3_256)
+    }
+
+    pub fn sha3_512() -> Hasher {
+        Hasher::new(HashAlgorithm::Sha3_512)
+    }
+
+    pub fn blake2b() -> Hasher {
+        Hasher::new(HashAlgorithm::Blake2b)
+    }
+
+    pub fn blake3() -> Hasher {
+        Hasher::new(HashAlgorithm::Blake3)
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+}
+
+impl Digester for Hasher {
+    fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    fn finalize(&mut self) -> Vec<u8> {
+        self.inner.finalize_reset().into_vec()
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    fn output_size(&self) -> usize {
+        self.inner.output_size()
+    }
+}
+
+impl Clone for Hasher {
+    fn clone(&self) -> Self {
+        Hasher {
+            algorithm: self.algorithm,
+            inner: self.inner.clone_sync(),
+        }
+    }
+}
+
+impl fmt::Debug for Hasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hasher")
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Hashes everything read through it.
+#[derive(Debug)]
+pub struct DigestReader<R, D> {
+    reader: R,
+    digester: D,
+}
+
+impl<R: Read, D: Digester> DigestReader<R, D> {
+    pub fn new(reader: R, digester: D) -> Self {
+        DigestReader { reader, digester }
+    }
+
+    /// Digest of the bytes read so far.
+    pub fn finalize(&mut self) -> Vec<u8> {
+        self.digester.finalize()
+    }
+
+    pub fn into_inner(self) -> (R, D) {
+        (self.reader, self.digester)
+    }
+}
+
+impl<R: Read, D: Digester> Read for DigestReader<R, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.digester.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+/// Hashes everything the inner writer accepted.
+#[derive(Debug)]
+pub struct DigestWriter<W, D> {
+    writer: W,
+    digester: D,
+}
+
+impl<W: Write, D: Digester> DigestWriter<W, D> {
+    pub fn new(writer: W, digester: D) -> Self {
+        DigestWriter { writer, digester }
+    }
+
+    /// Digest of the bytes written so far.
+    pub fn finalize(&mut self) -> Vec<u8> {
+        self.digester.finalize()
+    }
+
+    pub fn into_inner(self) -> (W, D) {
+        (self.writer, self.digester)
+    }
+}
+
+impl<W: Write, D: Digester> Write for DigestWriter<W, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.digester.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor, Read, Write};
+
+    use crate::digester::{DigestReader, DigestWriter, Digester, Hasher};
+    use crate::{hash_data, HashAlgorithm};
+
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn hasher_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Hasher>();
+
+        let mut hasher = Hasher::sha256();
+        hasher.update(b"a");
+        let digest = std::thread::spawn(move || {
+            hasher.update(b"bc");
+            hasher.finalize()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(hex::encode(digest), ABC_SHA256);
+    }
+
+    #[test]
+    fn update_finalize_reset() {
+        let mut hasher = Hasher::sha256();
+
+        hasher.update(b"a");
+        hasher.update(b"bc");
+        assert_eq!(hex::encode(hasher.finalize()), ABC_SHA256);
+
+        // finalize starts over
+        hasher.update(b"abc");
+        assert_eq!(hex::encode(hasher.finalize()), ABC_SHA256);
+
+        hasher.update(b"garbage");
+        hasher.reset();
+        hasher.update(b"abc");
+        assert_eq!(hex::encode(hasher.finalize()), ABC_SHA256);
+    }
+
+    #[test]
+    fn constructors_match_algorithms() {
+        let hashers = [
+            Hasher::md5(),
+            Hasher::sha1(),
+            Hasher::sha256(),
+            Hasher::sha384(),
+            Hasher::sha512(),
+            Hasher::sha3_256(),
+            Hasher::sha3_512(),
+            Hasher::blake2b(),
+            Hasher::blake3(),
+        ];
+
+        for (mut hasher, algorithm) in hashers.into_iter().zip(HashAlgorithm::ALL) {
+            assert_eq!(hasher.algorithm(), algorithm);
+            assert_eq!(hasher.output_size(), algorithm.output_size());
+            hasher.update(b"abc");
+            assert_eq!(
+                hasher.finalize(),
+                hash_data(b"abc", algorithm, None).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn read_and_write_adapters() {
+        let mut reader = DigestReader::new(Cursor::new(b"abc"), Hasher::sha256());
+        let mut content = vec![];
+        reader.read_to_end(&mut content).unwrap();
+
+        assert_eq!(content, b"abc");
+        assert_eq!(hex::encode(reader.finalize()), ABC_SHA256);
+
+        let mut hasher = Hasher::sha256();
+        let mut writer = DigestWriter::new(vec![], &mut hasher);
+        io::copy(&mut Cursor::new(b"abc"), &mut writer).unwrap();
+        writer.flush().unwrap();
+        let (written, _hasher) = writer.into_inner();
+
+        assert_eq!(written, b"abc");
+        assert_eq!(hex::encode(hasher.finalize()), ABC_SHA256);
+    }
+}
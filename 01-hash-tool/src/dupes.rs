@@ -0,0 +1,347 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+
+use serde::Serialize;
+
+use crate::output::OutputFormat;
+use crate::thread_pool::ThreadPool;
+use crate::walk::WalkOptions;
+use crate::{
+    hash_path, hash_reader, map_on_pool, new_hasher, pool_size, HashAlgorithm, HashToolError,
+};
+
+/// Bytes hashed from the start of every candidate before hashing whole files.
+pub const PARTIAL_SIZE: u64 = 4096;
+
+/// `hash-tool dupes` settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DupesOptions {
+    pub paths: Vec<String>,
+    pub algorithm: HashAlgorithm,
+    pub walk: WalkOptions,
+    pub jobs: usize,
+    /// Text or JSON, bare is treated as text.
+    pub format: OutputFormat,
+}
+
+/// Files with identical content.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateGroup {
+    /// Size of each file.
+    pub size: u64,
+    /// Hex encoded digest of each file.
+    pub digest: String,
+    pub paths: Vec<String>,
+}
+
+/// Groups `files` with identical content. Candidates are narrowed down by size first, then by a
+/// digest of their first `PARTIAL_SIZE` bytes, and only the remaining ones are hashed in full.
+/// Empty files are ignored. A file reached by more than one path, e.g. listed twice, inside two
+/// of the given directories or hard linked, is only counted once under the first path, it's not a
+/// copy of itself. Files which can't be read are left out and returned as errors.
+pub fn find_duplicates(
+    files: &[String],
+    algorithm: HashAlgorithm,
+    jobs: usize,
+) -> (Vec<DuplicateGroup>, Vec<HashToolError>) {
+    let mut errors = vec![];
+
+    let mut seen = HashSet::new();
+    let mut by_size: HashMap<u64, Vec<String>> = HashMap::new();
+    for file_path in files {
+        let metadata = fs::metadata(file_path).and_then(|metadata| {
+            file_id(file_path, &metadata).map(|file_id| (metadata.len(), file_id))
+        });
+        match metadata {
+            Ok((size, file_id)) if size > 0 && seen.insert(file_id) => {
+                by_size.entry(size).or_default().push(file_path.clone())
+            }
+            Ok(_metadata) => {}
+            Err(e) => errors.push(HashToolError::io(file_path, e)),
+        }
+    }
+
+    // both passes share one pool, each hashing all of its candidates at once
+    let pool = ThreadPool::new(pool_size(jobs));
+
+    let candidates: Vec<(u64, String)> = by_size
+        .into_iter()
+        .filter(|(_size, paths)| paths.len() > 1)
+        .flat_map(|(size, paths)| paths.into_iter().map(move |path| (size, path)))
+        .collect();
+    let paths: Vec<String> = candidates
+        .iter()
+        .map(|(_size, path)| path.clone())
+        .collect();
+    let partials = map_on_pool(&pool, &paths, move |file_path| {
+        hash_prefix(file_path, algorithm)
+    });
+
+    let mut by_partial: HashMap<(u64, Vec<u8>), Vec<String>> = HashMap::new();
+    for ((size, file_path), result) in candidates.into_iter().zip(partials) {
+        match result {
+            Ok(digest) => by_partial
+                .entry((size, digest))
+                .or_default()
+                .push(file_path),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    let mut groups = vec![];
+    let mut candidates = vec![];
+    for ((size, partial), paths) in by_partial {
+        if paths.len() < 2 {
+            continue;
+        }
+
+        // the partial digest already covers small files completely
+        if size <= PARTIAL_SIZE {
+            groups.push(DuplicateGroup {
+                size,
+                digest: hex::encode(partial),
+                paths,
+            });
+        } else {
+            candidates.extend(paths.into_iter().map(|path| (size, path)));
+        }
+    }
+
+    let paths: Vec<String> = candidates
+        .iter()
+        .map(|(_size, path)| path.clone())
+        .collect();
+    let digests = map_on_pool(&pool, &paths, move |file_path| {
+        hash_path(file_path, algorithm, None)
+    });
+
+    let mut by_digest: HashMap<(u64, Vec<u8>), Vec<String>> = HashMap::new();
+    for ((size, file_path), result) in candidates.into_iter().zip(digests) {
+        match result {
+            Ok(hash) => by_digest
+                .entry((size, hash.digest))
+                .or_default()
+                .push(file_path),
+            Err(e) => errors.push(e),
+        }
+    }
+    groups.extend(
+        by_digest
+            .into_iter()
+            .filter(|(_key, paths)| paths.len() > 1)
+            .map(|((size, digest), paths)| DuplicateGroup {
+                size,
+                digest: hex::encode(digest),
+                paths,
+            }),
+    );
+
+    // biggest waste of space first, stable order for the rest
+    for group in &mut groups {
+        group.paths.sort();
+    }
+    groups.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.paths.cmp(&b.paths)));
+
+    (groups, errors)
+}
+
+/// Identifies a file whatever path it's reached by.
+#[cfg(unix)]
+type FileId = (u64, u64);
+#[cfg(not(unix))]
+type FileId = std::path::PathBuf;
+
+#[cfg(unix)]
+fn file_id(_file_path: &str, metadata: &fs::Metadata) -> io::Result<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(file_path: &str, _metadata: &fs::Metadata) -> io::Result<FileId> {
+    fs::canonicalize(file_path)
+}
+
+fn hash_prefix(file_path: &str, algorithm: HashAlgorithm) -> Result<Vec<u8>, HashToolError> {
+    let hasher = new_hasher(algorithm, None)?;
+
+    File::open(file_path)
+        .and_then(|file| hash_reader(file.take(PARTIAL_SIZE), hasher))
+        .map(|hash| hash.digest)
+        .map_err(|e| HashToolError::io(file_path, e))
+}
+
+/// Text output has one `digest  path` line per file and an empty line between groups.
+pub fn write_groups(
+    out: &mut impl Write,
+    groups: &[DuplicateGroup],
+    format: OutputFormat,
+) -> io::Result<()> {
+    if format == OutputFormat::Json {
+        serde_json::to_writer_pretty(&mut *out, groups)?;
+        return writeln!(out);
+    }
+
+    for (i, group) in groups.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        for path in &group.paths {
+            writeln!(out, "{}  {path}", group.digest)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dupes::{find_duplicates, write_groups, DuplicateGroup, PARTIAL_SIZE};
+    use crate::output::OutputFormat;
+    use crate::test_dir::TestDir;
+    use crate::walk::{collect_files, WalkOptions};
+    use crate::HashAlgorithm;
+
+    /// Files under `paths`, walked like `dupes` does.
+    fn collect(paths: &[&str]) -> Vec<String> {
+        let paths: Vec<String> = paths.iter().map(|path| path.to_string()).collect();
+        let walk = WalkOptions {
+            recursive: true,
+            ..WalkOptions::default()
+        };
+
+        collect_files(&paths, &walk).unwrap()
+    }
+
+    fn prepare_tree() -> TestDir {
+        let root = TestDir::new("dupes");
+
+        let big = vec![b'x'; PARTIAL_SIZE as usize * 2];
+        let mut big_other_tail = big.clone();
+        *big_other_tail.last_mut().unwrap() = b'y';
+
+        root.write("a.txt", "same");
+        root.write("nested/b.txt", "same");
+        root.write("c.txt", "diff");
+        root.write("big-1.bin", &big);
+        root.write("nested/big-2.bin", &big);
+        root.write("big-3.bin", &big_other_tail);
+        root.write("empty-1", "");
+        root.write("empty-2", "");
+
+        root
+    }
+
+    #[test]
+    fn many_groups_on_any_number_of_jobs() {
+        let root = TestDir::new("dupes-many");
+        for group in 0..20u8 {
+            let content = vec![group; PARTIAL_SIZE as usize + 1];
+            root.write(&format!("{group}-a"), &content);
+            root.write(&format!("{group}-b"), &content);
+        }
+        let files = collect(&[&root.path_string()]);
+
+        let (groups, errors) = find_duplicates(&files, HashAlgorithm::Sha256, 1);
+
+        assert!(errors.is_empty());
+        assert_eq!(groups.len(), 20);
+        assert!(groups.iter().all(|group| group.paths.len() == 2));
+        assert_eq!(find_duplicates(&files, HashAlgorithm::Sha256, 4).0, groups);
+    }
+
+    #[test]
+    fn group_identical_files() {
+        let root = prepare_tree();
+        let files = collect(&[&root.path_string()]);
+
+        let (groups, errors) = find_duplicates(&files, HashAlgorithm::Sha256, 2);
+        let paths: Vec<Vec<String>> = groups
+            .iter()
+            .map(|group| {
+                group
+                    .paths
+                    .iter()
+                    .map(|path| path.strip_prefix(&root.path_string()).unwrap()[1..].to_string())
+                    .map(|path| path.replace('\\', "/"))
+                    .collect()
+            })
+            .collect();
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            paths,
+            vec![
+                vec!["big-1.bin", "nested/big-2.bin"],
+                vec!["a.txt", "nested/b.txt"]
+            ]
+        );
+        assert_eq!(groups[1].size, 4);
+    }
+
+    #[test]
+    fn count_each_file_once() {
+        let root = prepare_tree();
+        let a = root.join("a.txt").to_string_lossy().into_owned();
+        let nested = root.join("nested").to_string_lossy().into_owned();
+        let files = collect(&[&a, &a, &nested, &root.path_string(), &nested]);
+
+        let (groups, errors) = find_duplicates(&files, HashAlgorithm::Sha256, 2);
+
+        assert!(errors.is_empty());
+        assert_eq!(groups.len(), 2);
+        // the first path a file is reached by is the one reported
+        assert_eq!(
+            groups[1].paths,
+            vec![
+                a,
+                root.join("nested")
+                    .join("b.txt")
+                    .to_string_lossy()
+                    .into_owned()
+            ]
+        );
+        assert!(groups.iter().all(|group| group.paths.len() == 2));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_links_are_not_copies() {
+        let root = prepare_tree();
+        std::fs::hard_link(root.join("c.txt"), root.join("c-link.txt")).unwrap();
+        let files = collect(&[&root.path_string()]);
+
+        let (groups, _errors) = find_duplicates(&files, HashAlgorithm::Sha256, 2);
+
+        assert_eq!(groups.len(), 2);
+        assert!(groups
+            .iter()
+            .all(|group| !group.paths[0].ends_with("c.txt")));
+    }
+
+    #[test]
+    fn write_text_groups() {
+        let groups = vec![
+            DuplicateGroup {
+                size: 4,
+                digest: String::from("aa"),
+                paths: vec![String::from("x"), String::from("y")],
+            },
+            DuplicateGroup {
+                size: 1,
+                digest: String::from("bb"),
+                paths: vec![String::from("z"), String::from("w")],
+            },
+        ];
+        let mut out = vec![];
+
+        write_groups(&mut out, &groups, OutputFormat::Text).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "aa  x\naa  y\n\nbb  z\nbb  w\n"
+        );
+    }
+}
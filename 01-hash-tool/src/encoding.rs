@@ -0,0 +1,331 @@
+use std::str::FromStr;
+
+use data_encoding::{
+    Encoding, BASE32, BASE32_NOPAD, BASE64, BASE64URL, BASE64URL_NOPAD, BASE64_NOPAD, HEXLOWER,
+    HEXLOWER_PERMISSIVE, HEXUPPER,
+};
+
+use crate::{HashAlgorithm, HashToolError};
+
+/// Text representation of a digest.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum DigestEncoding {
+    #[default]
+    Hex,
+    HexUpper,
+    Base64,
+    /// URL safe alphabet without padding.
+    Base64Url,
+    /// RFC 4648 alphabet with padding, as printed by `base32`.
+    Base32,
+    /// Multihash (algorithm code, length, digest) in base58btc, as used by IPFS.
+    Multihash,
+    /// Subresource Integrity value, e.g. `sha384-<base64>`.
+    Sri,
+}
+
+impl DigestEncoding {
+    /// Rejects combinations which have no representation, SRI only defines the SHA-2 family.
+    pub fn check_algorithm(&self, algorithm: HashAlgorithm) -> Result<(), HashToolError> {
+        match (self, algorithm) {
+            (
+                DigestEncoding::Sri,
+                HashAlgorithm::Sha256 | HashAlgorithm::Sha384 | HashAlgorithm::Sha512,
+            ) => Ok(()),
+            (DigestEncoding::Sri, _) => Err(HashToolError::Usage(format!(
+                "SRI is only defined for sha256, sha384 and sha512, not for {algorithm}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Rejects SRI and multihash for anything but a plain digest of the content, they label the
+    /// value as one. `what` names the digest, e.g. "an HMAC".
+    pub fn check_plain_digest(&self, what: &str) -> Result<(), HashToolError> {
+        let name = match self {
+            DigestEncoding::Sri => "SRI",
+            DigestEncoding::Multihash => "multihash",
+            _ => return Ok(()),
+        };
+
+        Err(HashToolError::Usage(format!(
+            "{name} is only for plain digests of the content, not for {what}"
+        )))
+    }
+
+    pub fn encode(&self, digest: &[u8], algorithm: HashAlgorithm) -> String {
+        match self {
+            DigestEncoding::Hex => HEXLOWER.encode(digest),
+            DigestEncoding::HexUpper => HEXUPPER.encode(digest),
+            DigestEncoding::Base64 => BASE64.encode(digest),
+            DigestEncoding::Base64Url => BASE64URL_NOPAD.encode(digest),
+            DigestEncoding::Base32 => BASE32.encode(digest),
+            DigestEncoding::Multihash => bs58::encode(multihash(digest, algorithm)).into_string(),
+            DigestEncoding::Sri => format!("{}-{}", algorithm, BASE64.encode(digest)),
+        }
+    }
+}
+
+impl FromStr for DigestEncoding {
+    type Err = HashToolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // `hex` and `HEX` differ only by case, so the match is case sensitive
+        match s {
+            "hex" => Ok(DigestEncoding::Hex),
+            "HEX" => Ok(DigestEncoding::HexUpper),
+            "base64" => Ok(DigestEncoding::Base64),
+            "base64url" => Ok(DigestEncoding::Base64Url),
+            "base32" => Ok(DigestEncoding::Base32),
+            "multihash" => Ok(DigestEncoding::Multihash),
+            "sri" => Ok(DigestEncoding::Sri),
+            _ => Err(HashToolError::Usage(format!("unknown encoding '{s}'"))),
+        }
+    }
+}
+
+/// Decodes a digest written in any of the supported encodings, e.g. one copied from a download
+/// page. Only candidates of the right length for `algorithm` are accepted, except for valid hex,
+/// which is never read as another encoding.
+pub fn decode_digest(text: &str, algorithm: HashAlgorithm) -> Result<Vec<u8>, HashToolError> {
+    let text = text.trim();
+    let size = algorithm.output_size();
+    let invalid = || {
+        HashToolError::Usage(format!(
+            "'{text}' is not a {algorithm} digest in any supported encoding"
+        ))
+    };
+
+    if let Some((prefix, value)) = text.split_once('-') {
+        if let Ok(sri_algorithm) = prefix.parse::<HashAlgorithm>() {
+            if sri_algorithm != algorithm {
+                return Err(HashToolError::Usage(format!(
+                    "expected digest is {sri_algorithm}, but the file is hashed with {algorithm}"
+                )));
+            }
+            return decode_with(&[&BASE64], value, size).ok_or_else(invalid);
+        }
+    }
+
+    // a hex digest of another algorithm could pass for base64 of the right length
+    if let Ok(digest) = HEXLOWER_PERMISSIVE.decode(text.as_bytes()) {
+        if digest.len() == size {
+            return Ok(digest);
+        }
+        return Err(HashToolError::Usage(format!(
+            "'{text}' is {} bytes of hex, but a {algorithm} digest is {size} bytes",
+            digest.len()
+        )));
+    }
+
+    let encodings: [&Encoding; 6] = [
+        &BASE64,
+        &BASE64_NOPAD,
+        &BASE64URL,
+        &BASE64URL_NOPAD,
+        &BASE32,
+        &BASE32_NOPAD,
+    ];
+    if let Some(digest) = decode_with(&encodings, text, size) {
+        return Ok(digest);
+    }
+
+    // multihash of a zeroed digest has the code and length prefix we expect
+    let template = multihash(&vec![0; size], algorithm);
+    let prefix_len = template.len() - size;
+    match bs58::decode(text).into_vec() {
+        Ok(bytes)
+            if bytes.len() == template.len() && bytes[..prefix_len] == template[..prefix_len] =>
+        {
+            Ok(bytes[prefix_len..].to_vec())
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn decode_with(encodings: &[&Encoding], text: &str, size: usize) -> Option<Vec<u8>> {
+    encodings
+        .iter()
+        .filter_map(|encoding| encoding.decode(text.as_bytes()).ok())
+        .find(|digest| digest.len() == size)
+}
+
+/// Code of the algorithm in the multicodec table.
+pub fn multihash_code(algorithm: HashAlgorithm) -> u64 {
+    match algorithm {
+        HashAlgorithm::Md5 => 0xd5,
+        HashAlgorithm::Sha1 => 0x11,
+        HashAlgorithm::Sha256 => 0x12,
+        HashAlgorithm::Sha384 => 0x20,
+        HashAlgorithm::Sha512 => 0x13,
+        HashAlgorithm::Sha3_256 => 0x16,
+        HashAlgorithm::Sha3_512 => 0x14,
+        HashAlgorithm::Blake2b => 0xb240,
+        HashAlgorithm::Blake3 => 0x1e,
+    }
+}
+
+/// Binary multihash: varint code, varint digest length, digest.
+pub fn multihash(digest: &[u8], algorithm: HashAlgorithm) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(digest.len() + 4);
+    write_varint(&mut bytes, multihash_code(algorithm));
+    write_varint(&mut bytes, digest.len() as u64);
+    bytes.extend_from_slice(digest);
+    bytes
+}
+
+/// Unsigned LEB128, the varint flavour used by multiformats.
+pub(crate) fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::{decode_digest, multihash, DigestEncoding};
+    use crate::{hash_data, HashAlgorithm};
+
+    fn abc(algorithm: HashAlgorithm) -> Vec<u8> {
+        hash_data(b"abc", algorithm, None).unwrap()
+    }
+
+    #[test]
+    fn encode_sha256() {
+        let digest = abc(HashAlgorithm::Sha256);
+        let encode = |name: &str| {
+            let encoding: DigestEncoding = name.parse().unwrap();
+            encoding.encode(&digest, HashAlgorithm::Sha256)
+        };
+
+        assert_eq!(
+            encode("HEX"),
+            "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD"
+        );
+        assert_eq!(
+            encode("base64"),
+            "ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0="
+        );
+        assert_eq!(
+            encode("base64url"),
+            "ungWv48Bz-pBQUDeXa4iI7ADYaOWF3qctBD_YfIAFa0"
+        );
+        assert_eq!(
+            encode("base32"),
+            "XJ4BNP4PAHH6UQKBIDPF3LRCEOYAGYNDSYLXVHFUCD7WD4QACWWQ===="
+        );
+        assert_eq!(
+            encode("sri"),
+            "sha256-ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0="
+        );
+    }
+
+    #[test]
+    fn encode_sri_sha384() {
+        // example from the Subresource Integrity specification
+        let digest = hash_data(b"alert('Hello, world.');", HashAlgorithm::Sha384, None).unwrap();
+
+        assert_eq!(
+            DigestEncoding::Sri.encode(&digest, HashAlgorithm::Sha384),
+            "sha384-H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+eX6xO"
+        );
+    }
+
+    #[test]
+    fn encode_multihash() {
+        let digest = abc(HashAlgorithm::Sha256);
+
+        let bytes = multihash(&digest, HashAlgorithm::Sha256);
+        let blake2b = multihash(&abc(HashAlgorithm::Blake2b), HashAlgorithm::Blake2b);
+
+        assert_eq!(&bytes[..2], &[0x12, 0x20]);
+        assert_eq!(&bytes[2..], digest.as_slice());
+        // two byte varint code, then 64 as digest length
+        assert_eq!(&blake2b[..3], &[0xc0, 0xe4, 0x02]);
+        assert_eq!(blake2b[3], 0x40);
+        assert!(DigestEncoding::Multihash
+            .encode(&digest, HashAlgorithm::Sha256)
+            .starts_with("Qm"));
+    }
+
+    #[test]
+    fn sri_only_for_sha2() {
+        assert!(DigestEncoding::Sri
+            .check_algorithm(HashAlgorithm::Sha512)
+            .is_ok());
+        assert!(DigestEncoding::Sri
+            .check_algorithm(HashAlgorithm::Blake3)
+            .is_err());
+        assert!("hEx".parse::<DigestEncoding>().is_err());
+    }
+
+    #[test]
+    fn labelled_encodings_only_for_plain_digests() {
+        assert_eq!(
+            DigestEncoding::Sri
+                .check_plain_digest("an HMAC")
+                .unwrap_err()
+                .to_string(),
+            "SRI is only for plain digests of the content, not for an HMAC"
+        );
+        assert!(DigestEncoding::Multihash
+            .check_plain_digest("an HMAC")
+            .is_err());
+        assert!(DigestEncoding::Base64.check_plain_digest("an HMAC").is_ok());
+    }
+
+    #[test]
+    fn decode_any_encoding() {
+        for algorithm in HashAlgorithm::ALL {
+            let digest = abc(algorithm);
+
+            for encoding in [
+                DigestEncoding::Hex,
+                DigestEncoding::HexUpper,
+                DigestEncoding::Base64,
+                DigestEncoding::Base64Url,
+                DigestEncoding::Base32,
+                DigestEncoding::Multihash,
+            ] {
+                let text = encoding.encode(&digest, algorithm);
+
+                assert_eq!(
+                    decode_digest(&text, algorithm).unwrap(),
+                    digest,
+                    "{algorithm} {encoding:?}"
+                );
+            }
+        }
+
+        let sri = DigestEncoding::Sri.encode(&abc(HashAlgorithm::Sha384), HashAlgorithm::Sha384);
+        assert_eq!(
+            decode_digest(&sri, HashAlgorithm::Sha384).unwrap(),
+            abc(HashAlgorithm::Sha384)
+        );
+    }
+
+    #[test]
+    fn decode_invalid_digest() {
+        let sha256 = DigestEncoding::Hex.encode(&abc(HashAlgorithm::Sha256), HashAlgorithm::Sha256);
+        let sri = DigestEncoding::Sri.encode(&abc(HashAlgorithm::Sha256), HashAlgorithm::Sha256);
+
+        // right encoding, wrong length for the algorithm
+        assert!(decode_digest(&sha256, HashAlgorithm::Sha512).is_err());
+        // 64 characters are also 48 bytes of base64, the size of sha384
+        assert_eq!(
+            decode_digest(&sha256, HashAlgorithm::Sha384)
+                .unwrap_err()
+                .to_string(),
+            format!("'{sha256}' is 32 bytes of hex, but a sha384 digest is 48 bytes")
+        );
+        assert!(decode_digest("not a digest", HashAlgorithm::Sha256).is_err());
+        assert_eq!(
+            decode_digest(&sri, HashAlgorithm::Sha512)
+                .unwrap_err()
+                .to_string(),
+            "expected digest is sha256, but the file is hashed with sha512"
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+/// Process exit code when a checksum does not match, which is not an error of the tool itself.
+pub const EXIT_MISMATCH: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_NOT_FOUND: i32 = 3;
+pub const EXIT_PERMISSION_DENIED: i32 = 4;
+pub const EXIT_IO: i32 = 5;
+
+#[derive(Debug)]
+pub enum HashToolError {
+    /// Command line arguments could not be understood.
+    Usage(String),
+    /// Parsing the command line stopped, on a usage error or to print help or the version.
+    Cli(clap::Error),
+    UnknownAlgorithm(String),
+    InvalidPattern(String),
+    /// Key doesn't fit the keyed mode of the selected algorithm.
+    InvalidKey(&'static str),
+    /// Text isn't in any of the password hash formats we can verify.
+    InvalidPasswordHash(String),
+    /// Reading `path` failed, `-` stands for the standard input.
+    Io {
+        path: String,
+        source: io::Error,
+    },
+}
+
+impl HashToolError {
+    pub fn io(path: &str, source: io::Error) -> Self {
+        HashToolError::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            HashToolError::Usage(_)
+            | HashToolError::UnknownAlgorithm(_)
+            | HashToolError::InvalidPattern(_)
+            | HashToolError::InvalidKey(_)
+            | HashToolError::InvalidPasswordHash(_) => EXIT_USAGE,
+            // 0 after printing help or the version
+            HashToolError::Cli(error) => error.exit_code(),
+            HashToolError::Io { source, .. } => match source.kind() {
+                ErrorKind::NotFound => EXIT_NOT_FOUND,
+                ErrorKind::PermissionDenied => EXIT_PERMISSION_DENIED,
+                _ => EXIT_IO,
+            },
+        }
+    }
+}
+
+impl fmt::Display for HashToolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashToolError::Usage(message) => write!(f, "{message}"),
+            HashToolError::Cli(error) => write!(f, "{error}"),
+            HashToolError::UnknownAlgorithm(name) => write!(f, "unknown hash algorithm '{name}'"),
+            HashToolError::InvalidPattern(pattern) => {
+                write!(f, "invalid exclude pattern '{pattern}'")
+            }
+            HashToolError::InvalidKey(reason) => write!(f, "invalid key: {reason}"),
+            HashToolError::InvalidPasswordHash(hash) => {
+                write!(f, "'{hash}' is not a supported password hash")
+            }
+            HashToolError::Io { path, source } => match source.kind() {
+                ErrorKind::NotFound => write!(f, "{path}: no such file or directory"),
+                ErrorKind::PermissionDenied => write!(f, "{path}: permission denied"),
+                ErrorKind::IsADirectory => write!(f, "{path}: is a directory"),
+                _ => write!(f, "{path}: {source}"),
+            },
+        }
+    }
+}
+
+impl Error for HashToolError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HashToolError::Cli(error) => Some(error),
+            HashToolError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::io::{self, ErrorKind};
+
+    use crate::error::*;
+
+    #[test]
+    fn exit_codes_per_error_class() {
+        let not_found = HashToolError::io("file", io::Error::from(ErrorKind::NotFound));
+        let denied = HashToolError::io("file", io::Error::from(ErrorKind::PermissionDenied));
+        let directory = HashToolError::io("dir", io::Error::from(ErrorKind::IsADirectory));
+
+        assert_eq!(not_found.exit_code(), EXIT_NOT_FOUND);
+        assert_eq!(denied.exit_code(), EXIT_PERMISSION_DENIED);
+        assert_eq!(directory.exit_code(), EXIT_IO);
+        assert_eq!(HashToolError::Usage(String::new()).exit_code(), EXIT_USAGE);
+        assert_eq!(HashToolError::InvalidKey("").exit_code(), EXIT_USAGE);
+    }
+
+    #[test]
+    fn io_error_message_and_source() {
+        let error = HashToolError::io("secret.txt", io::Error::from(ErrorKind::PermissionDenied));
+
+        assert_eq!(error.to_string(), "secret.txt: permission denied");
+        assert_eq!(
+            error
+                .source()
+                .and_then(|e| e.downcast_ref::<io::Error>())
+                .map(|e| e.kind()),
+            Some(ErrorKind::PermissionDenied)
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::fs::File;
+use std::io::{self, Cursor, Read};
+use std::str::FromStr;
+
+use data_encoding::BASE32_NOPAD;
+
+use crate::encoding::{multihash, write_varint};
+use crate::{
+    hash_data, hash_reader, new_hasher, Digester, HashAlgorithm, HashToolError, Hasher, STDIN_PATH,
+};
+
+/// Multicodec code of raw binary leaves.
+const RAW_CODEC: u64 = 0x55;
+
+/// Largest content `ipfs add` keeps in one block, bigger files are split into a DAG whose CID
+/// this tool can't give.
+pub const CID_MAX_SIZE: u64 = 256 * 1024;
+
+/// Identifier other tools give to the content of a file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fingerprint {
+    /// Blob object id as printed by `git hash-object`.
+    GitSha1,
+    /// Blob object id in repositories using the SHA-256 object format.
+    GitSha256,
+    /// CIDv1 of a raw leaf with a SHA-256 multihash, as `ipfs add --raw-leaves --cid-version 1`
+    /// gives for files that fit in one block.
+    Cid,
+}
+
+impl Fingerprint {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Fingerprint::GitSha1 => "git-sha1",
+            Fingerprint::GitSha256 => "git-sha256",
+            Fingerprint::Cid => "cid",
+        }
+    }
+
+    pub fn compute(&self, data: &[u8]) -> String {
+        match self {
+            Fingerprint::GitSha1 => hex::encode(git_blob_id(data, HashAlgorithm::Sha1)),
+            Fingerprint::GitSha256 => hex::encode(git_blob_id(data, HashAlgorithm::Sha256)),
+            Fingerprint::Cid => cid_v1_raw(data),
+        }
+    }
+
+    /// Fingerprint and size of a file, streamed. `-` reads the standard input, which is held in
+    /// memory as git needs the length up front.
+    pub fn compute_path(&self, file_path: &str) -> Result<(String, u64), HashToolError> {
+        let (reader, len): (Box<dyn Read>, u64) = if file_path == STDIN_PATH {
+            let mut data = vec![];
+            io::stdin()
+                .read_to_end(&mut data)
+                .map_err(|e| HashToolError::io(file_path, e))?;
+            let len = data.len() as u64;
+            (Box::new(Cursor::new(data)), len)
+        } else {
+            let file = File::open(file_path).map_err(|e| HashToolError::io(file_path, e))?;
+            let len = file
+                .metadata()
+                .map_err(|e| HashToolError::io(file_path, e))?
+                .len();
+            (Box::new(file), len)
+        };
+
+        let hasher = match self {
+            Fingerprint::GitSha1 => git_blob_hasher(len, HashAlgorithm::Sha1),
+            Fingerprint::GitSha256 => git_blob_hasher(len, HashAlgorithm::Sha256),
+            Fingerprint::Cid if len > CID_MAX_SIZE => {
+                return Err(HashToolError::Usage(format!(
+                    "{file_path}: cid is only given for up to {CID_MAX_SIZE} bytes, \
+                     IPFS splits bigger files into several blocks"
+                )))
+            }
+            Fingerprint::Cid => new_hasher(HashAlgorithm::Sha256, None)?,
+        };
+        let hash = hash_reader(reader, hasher).map_err(|e| HashToolError::io(file_path, e))?;
+        if hash.size != len {
+            let changed = io::Error::other("changed while being read");
+            return Err(HashToolError::io(file_path, changed));
+        }
+
+        let fingerprint = match self {
+            Fingerprint::GitSha1 | Fingerprint::GitSha256 => hex::encode(hash.digest),
+            Fingerprint::Cid => cid_v1_raw_from_digest(&hash.digest),
+        };
+        Ok((fingerprint, hash.size))
+    }
+}
+
+impl FromStr for Fingerprint {
+    type Err = HashToolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "git" | "git-sha1" => Ok(Fingerprint::GitSha1),
+            "git-sha256" => Ok(Fingerprint::GitSha256),
+            "cid" | "cidv1" => Ok(Fingerprint::Cid),
+            _ => Err(HashToolError::Usage(format!("unknown fingerprint '{s}'"))),
+        }
+    }
+}
+
+/// Digest of the `blob <len>\0` header followed by the content.
+pub fn git_blob_id(data: &[u8], algorithm: HashAlgorithm) -> Vec<u8> {
+    let mut hasher = git_blob_hasher(data.len() as u64, algorithm);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Hasher fed with the header of a blob of `len` bytes, the content goes next.
+fn git_blob_hasher(len: u64, algorithm: HashAlgorithm) -> Hasher {
+    // only keyed hashers can fail
+    let mut hasher = new_hasher(algorithm, None).unwrap();
+    hasher.update(format!("blob {len}\0").as_bytes());
+    hasher
+}
+
+/// Version, raw codec and SHA-256 multihash, in lower case base32 with the `b` multibase prefix.
+pub fn cid_v1_raw(data: &[u8]) -> String {
+    cid_v1_raw_from_digest(&hash_data(data, HashAlgorithm::Sha256, None).unwrap())
+}
+
+fn cid_v1_raw_from_digest(digest: &[u8]) -> String {
+    let mut cid = vec![];
+    write_varint(&mut cid, 1);
+    write_varint(&mut cid, RAW_CODEC);
+    cid.extend(multihash(digest, HashAlgorithm::Sha256));
+
+    format!("b{}", BASE32_NOPAD.encode(&cid).to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fingerprint::{Fingerprint, CID_MAX_SIZE};
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn git_blob_ids() {
+        let hello = b"hello world\n";
+
+        assert_eq!(
+            Fingerprint::GitSha1.compute(b""),
+            "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"
+        );
+        assert_eq!(
+            Fingerprint::GitSha1.compute(hello),
+            "3b18e512dba79e4c8300dd08aeb37f8e728b8dad"
+        );
+        assert_eq!(
+            Fingerprint::GitSha256.compute(b""),
+            "473a0f4c3be8a93681a267e3b1e9a7dcda1185436fe141f7749120a303721813"
+        );
+        assert_eq!(
+            Fingerprint::GitSha256.compute(hello),
+            "0bd69098bd9b9cc5934a610ab65da429b525361147faa7b5b922919e9a23143d"
+        );
+    }
+
+    #[test]
+    fn cid_of_raw_leaf() {
+        assert_eq!(
+            Fingerprint::Cid.compute(b""),
+            "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"
+        );
+        assert_eq!(
+            Fingerprint::Cid.compute(b"hello world\n"),
+            "bafkreifjjcie6lypi6ny7amxnfftagclbuxndqonfipmb64f2km2devei4"
+        );
+        assert_eq!("git".parse::<Fingerprint>().unwrap(), Fingerprint::GitSha1);
+        assert!("svn".parse::<Fingerprint>().is_err());
+    }
+
+    #[test]
+    fn fingerprint_files() {
+        let dir = TestDir::new("fingerprint");
+        dir.write("hello", "hello world\n");
+        dir.write("big", vec![0; CID_MAX_SIZE as usize + 1]);
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+        for fingerprint in [
+            Fingerprint::GitSha1,
+            Fingerprint::GitSha256,
+            Fingerprint::Cid,
+        ] {
+            assert_eq!(
+                fingerprint.compute_path(&path("hello")).unwrap(),
+                (fingerprint.compute(b"hello world\n"), 12)
+            );
+        }
+        assert_eq!(
+            Fingerprint::GitSha1.compute_path(&path("big")).unwrap().1,
+            CID_MAX_SIZE + 1
+        );
+        assert!(Fingerprint::Cid.compute_path(&path("big")).is_err());
+        assert!(Fingerprint::GitSha1.compute_path(&path("missing")).is_err());
+    }
+}
@@ -1,49 +1,322 @@
-use std::fs;
+use std::fs::{self, File};
+use std::io::{self, BufReader, ErrorKind, Read};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use sha2::{Digest, Sha256};
-use sha2::digest::consts::U32;
-use sha2::digest::generic_array::GenericArray;
+use clap::Parser;
+use clap_complete::Shell;
+use subtle::ConstantTimeEq;
 
-#[derive(Debug, PartialEq)]
+mod algorithm;
+pub mod bench;
+pub mod check;
+mod cli;
+pub mod dupes;
+mod digester;
+pub mod encoding;
+pub mod fingerprint;
+pub mod manifest;
+pub mod merkle;
+mod error;
+pub mod normalize;
+pub mod output;
+pub mod password;
+pub mod progress;
+#[cfg(test)]
+mod test_dir;
+mod thread_pool;
+pub mod walk;
+
+pub use algorithm::*;
+pub use cli::*;
+pub use digester::*;
+pub use error::*;
+use bench::BenchOptions;
+use cli::HashArgs;
+use dupes::DupesOptions;
+use encoding::DigestEncoding;
+use fingerprint::Fingerprint;
+use manifest::{DiffOptions, SnapshotOptions};
+use normalize::{NormalizingReader, TextNormalization};
+use output::OutputFormat;
+use password::PasswordCommand;
+use progress::{Progress, ProgressReader};
+use thread_pool::ThreadPool;
+use walk::WalkOptions;
+
+#[derive(Debug, Default, PartialEq)]
 pub struct Config {
-    pub file_path: String,
+    pub file_paths: Vec<String>,
+    pub algorithm: HashAlgorithm,
+    /// Checksum file to verify, instead of hashing `file_paths`.
+    pub check_file: Option<String>,
+    pub walk: WalkOptions,
+    /// Number of files hashed concurrently, `0` means one per available core.
+    pub jobs: usize,
+    /// Switches hashing into the keyed (HMAC) mode.
+    pub key: Option<KeySource>,
+    pub format: OutputFormat,
+    pub encoding: DigestEncoding,
+    /// Digest the single hashed file must match, in any supported encoding.
+    pub expect: Option<String>,
+    /// Digest files as the Merkle root over fixed-size chunks.
+    pub merkle: bool,
+    /// Bytes per chunk for `merkle`, 0 picks the default.
+    pub chunk_size: usize,
+    /// Where to write the chunk digests of the single hashed file.
+    pub chunk_manifest: Option<String>,
+    /// Print what git or IPFS would call the file instead of a digest.
+    pub fingerprint: Option<Fingerprint>,
+    /// Never draw the progress bar, which is otherwise shown when stdout is a terminal.
+    pub no_progress: bool,
+    /// Report size, time and throughput of every file on stderr.
+    pub stats: bool,
+    /// Normalize text files before hashing, so line endings and the like don't change the digest.
+    pub text: TextNormalization,
 }
 
-impl Config {
-    pub fn build(mut args: impl Iterator<Item = String>) -> Result<Config, &'static str> {
-        // skip program name
-        let p_name = args.next().unwrap();
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeySource {
+    /// Key given directly on the command line, used as UTF-8 bytes.
+    Inline(String),
+    /// Path of a file whose raw content is the key.
+    File(String),
+}
+
+impl KeySource {
+    pub fn load(&self) -> Result<Vec<u8>, HashToolError> {
+        match self {
+            KeySource::Inline(key) => Ok(key.as_bytes().to_vec()),
+            KeySource::File(path) => fs::read(path).map_err(|e| HashToolError::io(path, e)),
+        }
+    }
+}
 
-        println!("Running program '{}'", p_name);
+/// What to do, hashing files unless the first argument names a subcommand.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Hash(Config),
+    Password(PasswordCommand),
+    Bench(BenchOptions),
+    Dupes(DupesOptions),
+    Snapshot(SnapshotOptions),
+    Diff(DiffOptions),
+    /// Print the completion script for a shell.
+    Completions(Shell),
+}
 
-        let file_path = match args.next() {
-            Some(arg) => arg,
-            None => return Err("No file path provided!"),
-        };
+impl Command {
+    pub fn build(args: impl Iterator<Item = String>) -> Result<Command, HashToolError> {
+        cli::parse(args)
+    }
+}
 
-        Ok(Config { file_path })
+impl Config {
+    /// Arguments of the `hash` subcommand, starting with the program name.
+    pub fn build(args: impl Iterator<Item = String>) -> Result<Config, HashToolError> {
+        HashArgs::try_parse_from(args)
+            .map_err(HashToolError::Cli)?
+            .into_config()
     }
 }
 
-pub fn read_file(file_path: &String) -> Result<Vec<u8>, &'static str> {
-    let file = match fs::read(&file_path) {
+pub fn read_file(file_path: &String) -> Result<Vec<u8>, HashToolError> {
+    let file = match fs::read(file_path) {
         Ok(content) => content,
-        Err(_e) => return Err("FAILED_FILE_READ"),
+        Err(e) => return Err(HashToolError::io(file_path, e)),
     };
 
     Ok(file)
 }
 
-pub fn hash_data(data: Vec<u8>) -> GenericArray<u8, U32> {
-    let mut hasher = Sha256::new();
+/// Size of the buffer used when hashing files and streams.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Path which stands for the standard input instead of a file.
+pub const STDIN_PATH: &str = "-";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDigest {
+    pub digest: Vec<u8>,
+    /// Number of bytes hashed.
+    pub size: u64,
+    /// Time spent reading and hashing.
+    pub elapsed: Duration,
+}
+
+/// Plain hasher for `algorithm`, or the keyed one when a `key` is given.
+pub fn new_hasher(algorithm: HashAlgorithm, key: Option<&[u8]>) -> Result<Hasher, HashToolError> {
+    match key {
+        Some(key) => Hasher::keyed(algorithm, key),
+        None => Ok(Hasher::new(algorithm)),
+    }
+}
+
+pub fn hash_data(
+    data: &[u8],
+    algorithm: HashAlgorithm,
+    key: Option<&[u8]>,
+) -> Result<Vec<u8>, HashToolError> {
+    let mut hasher = new_hasher(algorithm, key)?;
     hasher.update(data);
-    let result = hasher.finalize();
-    result
+    Ok(hasher.finalize())
+}
+
+/// Compares two digests in constant time, so the comparison doesn't leak how many leading bytes
+/// matched.
+pub fn digest_matches(computed: &[u8], expected: &[u8]) -> bool {
+    computed.ct_eq(expected).into()
+}
+
+/// Hashes everything from `reader`, feeding the hasher in `CHUNK_SIZE` pieces so the input is
+/// never held in memory as a whole.
+pub fn hash_reader(
+    mut reader: impl Read,
+    mut hasher: impl Digester,
+) -> io::Result<FileDigest> {
+    let started = Instant::now();
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let mut size = 0;
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+
+    Ok(FileDigest {
+        digest: hasher.finalize(),
+        size,
+        elapsed: started.elapsed(),
+    })
+}
+
+/// Streaming counterpart of `read_file` + `hash_data`, `-` reads from the standard input.
+pub fn hash_path(
+    file_path: &str,
+    algorithm: HashAlgorithm,
+    key: Option<&[u8]>,
+) -> Result<FileDigest, HashToolError> {
+    let text = TextNormalization::default();
+    hash_path_with_progress(file_path, algorithm, key, &Progress::default(), text)
+}
+
+fn hash_path_with_progress(
+    file_path: &str,
+    algorithm: HashAlgorithm,
+    key: Option<&[u8]>,
+    progress: &Progress,
+    text: TextNormalization,
+) -> Result<FileDigest, HashToolError> {
+    let hasher = new_hasher(algorithm, key)?;
+
+    let reader: Box<dyn Read> = if file_path == STDIN_PATH {
+        Box::new(io::stdin().lock())
+    } else {
+        match File::open(file_path) {
+            Ok(file) => Box::new(file),
+            Err(e) => return Err(HashToolError::io(file_path, e)),
+        }
+    };
+    // progress counts the bytes read, not the normalized ones
+    let reader = ProgressReader::new(reader, progress.clone());
+
+    let result = if text.is_enabled() {
+        hash_reader(NormalizingReader::new(BufReader::new(reader), text), hasher)
+    } else {
+        hash_reader(reader, hasher)
+    };
+
+    result.map_err(|e| HashToolError::io(file_path, e))
+}
+
+/// Hashes every file on a pool of `jobs` threads (`0` picks one per core). Results are returned
+/// in the order of `files`, no matter which one finished first. Every byte read is added to
+/// `progress`. Files are normalized as `text` while being hashed.
+pub fn hash_files(
+    files: &[String],
+    algorithm: HashAlgorithm,
+    key: Option<&[u8]>,
+    jobs: usize,
+    progress: &Progress,
+    text: TextNormalization,
+) -> Vec<Result<FileDigest, HashToolError>> {
+    let jobs = pool_size(jobs);
+
+    if jobs == 1 || files.len() < 2 {
+        return files
+            .iter()
+            .map(|file_path| hash_path_with_progress(file_path, algorithm, key, progress, text))
+            .collect();
+    }
+
+    let key: Option<Arc<[u8]>> = key.map(Arc::from);
+    let progress = progress.clone();
+    let pool = ThreadPool::new(jobs.min(files.len()));
+    map_on_pool(&pool, files, move |file_path| {
+        hash_path_with_progress(file_path, algorithm, key.as_deref(), &progress, text)
+    })
+}
+
+/// Number of threads for `--jobs`, `0` picks one per core.
+pub(crate) fn pool_size(jobs: usize) -> usize {
+    match jobs {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+}
+
+/// Calls `f` for every file on `pool` and returns the results in the order of `files`, no matter
+/// which one finished first.
+pub(crate) fn map_on_pool<T, F>(pool: &ThreadPool, files: &[String], f: F) -> Vec<T>
+where
+    T: Send + 'static,
+    F: Fn(&str) -> T + Send + Sync + 'static,
+{
+    let f = Arc::new(f);
+    let (sender, receiver) = mpsc::channel();
+    for (index, file_path) in files.iter().enumerate() {
+        let sender = sender.clone();
+        let file_path = file_path.clone();
+        let f = Arc::clone(&f);
+
+        pool.execute(move || {
+            sender.send((index, f(&file_path))).unwrap();
+        });
+    }
+    // every job drops its sender when done, which ends the receiving once all of them are
+    drop(sender);
+
+    let mut results: Vec<_> = receiver.into_iter().collect();
+    results.sort_by_key(|(index, _result)| *index);
+    results.into_iter().map(|(_index, result)| result).collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Config, hash_data, read_file};
+    use std::error::Error;
+    use std::io::Cursor;
+
+    use clap::error::ErrorKind;
+
+    use crate::encoding::DigestEncoding;
+    use crate::fingerprint::Fingerprint;
+    use crate::normalize::TextNormalization;
+    use crate::output::OutputFormat;
+    use crate::progress::Progress;
+    use crate::walk::WalkOptions;
+    use crate::{
+        CHUNK_SIZE, Config, EXIT_IO, EXIT_NOT_FOUND, EXIT_USAGE, FileDigest, HashAlgorithm,
+        HashToolError, Hasher, KeySource, digest_matches, hash_data, hash_files, hash_path, hash_reader,
+        read_file,
+    };
 
     #[test]
     fn parse_config() {
@@ -57,19 +330,335 @@ mod tests {
         assert_eq!(
             parsed_config,
             Config {
-                file_path: String::from("file-path")
+                file_paths: vec![String::from("file-path")],
+                algorithm: HashAlgorithm::Sha256,
+                check_file: None,
+                walk: WalkOptions::default(),
+                jobs: 0,
+                key: None,
+                format: OutputFormat::Text,
+                encoding: DigestEncoding::Hex,
+                expect: None,
+                merkle: false,
+                chunk_size: 0,
+                chunk_manifest: None,
+                fingerprint: None,
+                no_progress: false,
+                stats: false,
+                text: TextNormalization::default(),
+            }
+        )
+    }
+
+    #[test]
+    fn parse_config_with_algorithm() {
+        let args = vec![
+            String::from("program-name"),
+            String::from("--algorithm"),
+            String::from("blake3"),
+            String::from("file-path"),
+        ]
+        .into_iter();
+
+        let parsed_config = Config::build(args).unwrap();
+
+        assert_eq!(
+            parsed_config,
+            Config {
+                file_paths: vec![String::from("file-path")],
+                algorithm: HashAlgorithm::Blake3,
+                ..Config::default()
+            }
+        )
+    }
+
+    #[test]
+    fn parse_config_check_mode() {
+        let args = vec![
+            String::from("program-name"),
+            String::from("--check"),
+            String::from("SHA256SUMS"),
+        ]
+        .into_iter();
+
+        let parsed_config = Config::build(args).unwrap();
+
+        assert_eq!(
+            parsed_config,
+            Config {
+                check_file: Some(String::from("SHA256SUMS")),
+                ..Config::default()
             }
         )
     }
 
+    #[test]
+    fn parse_config_many_paths_recursive() {
+        let args = vec![
+            String::from("program-name"),
+            String::from("-r"),
+            String::from("dir-1"),
+            String::from("--exclude"),
+            String::from("*.tmp"),
+            String::from("file-path"),
+            String::from("--follow-symlinks"),
+            String::from("--jobs"),
+            String::from("4"),
+            String::from("--stats"),
+        ]
+        .into_iter();
+
+        let parsed_config = Config::build(args).unwrap();
+
+        assert_eq!(
+            parsed_config,
+            Config {
+                file_paths: vec![String::from("dir-1"), String::from("file-path")],
+                jobs: 4,
+                walk: WalkOptions {
+                    recursive: true,
+                    follow_symlinks: true,
+                    exclude: vec![String::from("*.tmp")],
+                },
+                stats: true,
+                ..Config::default()
+            }
+        )
+    }
+
+    #[test]
+    fn parse_config_with_key() {
+        let args = vec![
+            String::from("program-name"),
+            String::from("--key-file"),
+            String::from("secret.key"),
+            String::from("file-path"),
+        ]
+        .into_iter();
+
+        let parsed_config = Config::build(args).unwrap();
+
+        assert_eq!(
+            parsed_config.key,
+            Some(KeySource::File(String::from("secret.key")))
+        );
+    }
+
+    fn cli_error_kind(result: Result<Config, HashToolError>) -> ErrorKind {
+        match result {
+            Err(HashToolError::Cli(e)) => e.kind(),
+            other => panic!("expected a command line error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_config_with_two_keys() {
+        let args = vec![
+            String::from("program-name"),
+            String::from("--hmac-key"),
+            String::from("secret"),
+            String::from("--key-file"),
+            String::from("secret.key"),
+            String::from("file-path"),
+        ]
+        .into_iter();
+
+        assert_eq!(
+            cli_error_kind(Config::build(args)),
+            ErrorKind::ArgumentConflict
+        );
+    }
+
+    #[test]
+    fn parse_config_with_format() {
+        let args = vec![
+            String::from("program-name"),
+            String::from("--format"),
+            String::from("json"),
+            String::from("--encoding"),
+            String::from("sri"),
+            String::from("file-path"),
+        ]
+        .into_iter();
+
+        let parsed_config = Config::build(args).unwrap();
+
+        assert_eq!(parsed_config.format, OutputFormat::Json);
+        assert_eq!(parsed_config.encoding, DigestEncoding::Sri);
+    }
+
+    #[test]
+    fn parse_config_expect_needs_one_file() {
+        let args = vec![
+            String::from("program-name"),
+            String::from("--expect"),
+            String::from("cd6c8edf"),
+            String::from("file-1"),
+            String::from("file-2"),
+        ]
+        .into_iter();
+
+        let error = Config::build(args).unwrap_err();
+
+        assert!(error.to_string().contains("--expect needs exactly one file path"));
+        assert_eq!(error.exit_code(), EXIT_USAGE);
+    }
+
+    #[test]
+    fn parse_config_merkle() {
+        let args = vec![
+            String::from("program-name"),
+            String::from("--merkle"),
+            String::from("--chunk-size"),
+            String::from("4096"),
+            String::from("file-path"),
+        ]
+        .into_iter();
+
+        let parsed_config = Config::build(args).unwrap();
+
+        assert_eq!(
+            parsed_config,
+            Config {
+                file_paths: vec![String::from("file-path")],
+                merkle: true,
+                chunk_size: 4096,
+                ..Config::default()
+            }
+        );
+    }
+
+    #[test]
+    fn parse_config_chunk_size_without_merkle() {
+        let args = vec![
+            String::from("program-name"),
+            String::from("--chunk-size"),
+            String::from("4096"),
+            String::from("file-path"),
+        ]
+        .into_iter();
+
+        assert_eq!(
+            cli_error_kind(Config::build(args)),
+            ErrorKind::MissingRequiredArgument
+        );
+    }
+
+    #[test]
+    fn parse_config_expect_with_merkle() {
+        // --expect compares the plain digest, which is not the Merkle root
+        let args = ["program-name", "--merkle", "--expect", "abcd", "file-path"].map(String::from);
+
+        assert_eq!(
+            cli_error_kind(Config::build(args.into_iter())),
+            ErrorKind::ArgumentConflict
+        );
+    }
+
+    #[test]
+    fn parse_config_fingerprint() {
+        let args = vec![
+            String::from("program-name"),
+            String::from("--fingerprint"),
+            String::from("cid"),
+            String::from("file-path"),
+        ]
+        .into_iter();
+
+        assert_eq!(
+            Config::build(args).unwrap().fingerprint,
+            Some(Fingerprint::Cid)
+        );
+    }
+
+    #[test]
+    fn parse_config_fingerprint_conflicts() {
+        for other in [["--expect", "abcd"], ["-a", "sha512"], ["--encoding", "base64"]] {
+            let args = ["program-name", "--fingerprint", "git", "file-path"]
+                .into_iter()
+                .chain(other)
+                .map(String::from);
+
+            assert_eq!(
+                cli_error_kind(Config::build(args)),
+                ErrorKind::ArgumentConflict,
+                "{other:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_config_text() {
+        let args = ["program-name", "--text", "crlf,bom", "file-path"].map(String::from);
+
+        assert_eq!(
+            Config::build(args.into_iter()).unwrap().text,
+            TextNormalization {
+                crlf: true,
+                bom: true,
+                ..TextNormalization::default()
+            }
+        );
+
+        let args = ["program-name", "--text", "all", "--merkle", "file-path"].map(String::from);
+        assert_eq!(
+            cli_error_kind(Config::build(args.into_iter())),
+            ErrorKind::ArgumentConflict
+        );
+    }
+
+    #[test]
+    fn parse_config_without_file_path() {
+        let args = vec![String::from("program-name")].into_iter();
+
+        let error = Config::build(args).unwrap_err();
+
+        assert!(matches!(
+            &error,
+            HashToolError::Cli(e) if e.kind() == ErrorKind::MissingRequiredArgument
+        ));
+        assert_eq!(error.exit_code(), EXIT_USAGE);
+    }
+
+    #[test]
+    fn parse_config_unknown_algorithm() {
+        let args = vec![
+            String::from("program-name"),
+            String::from("file-path"),
+            String::from("-a"),
+            String::from("crc32"),
+        ]
+        .into_iter();
+
+        // clap keeps the error of `HashAlgorithm::from_str` as its source
+        let error = Config::build(args).unwrap_err();
+        let source = error
+            .source()
+            .and_then(Error::source)
+            .and_then(|e| e.downcast_ref::<HashToolError>());
+
+        assert!(matches!(source, Some(HashToolError::UnknownAlgorithm(name)) if name == "crc32"));
+        assert_eq!(error.exit_code(), EXIT_USAGE);
+    }
+
     #[test]
     fn read_file_failed() {
-        let error: &str = match read_file(&String::from("non-existent")) {
-            Ok(_f) => "can't happen",
+        let error = match read_file(&String::from("non-existent")) {
+            Ok(_f) => panic!("can't happen"),
             Err(e) => e,
         };
 
-        assert_eq!(error, "FAILED_FILE_READ");
+        assert!(matches!(&error, HashToolError::Io { path, .. } if path == "non-existent"));
+        assert_eq!(error.to_string(), "non-existent: no such file or directory");
+        assert_eq!(error.exit_code(), EXIT_NOT_FOUND);
+    }
+
+    #[test]
+    fn read_directory_failed() {
+        let error = read_file(&String::from("src")).unwrap_err();
+
+        assert_eq!(error.to_string(), "src: is a directory");
+        assert_eq!(error.exit_code(), EXIT_IO);
     }
 
     #[test]
@@ -84,8 +673,230 @@ mod tests {
         let file_content = read_file(&String::from("test-file-1.txt")).unwrap();
         let expected_hash = "cd6c8edf218a2c38d46d5b0ecf235a71185dc11f57735f607cdcc5e5339b2c3c";
 
-        let hash = hash_data(file_content);
+        let hash = hash_data(&file_content, HashAlgorithm::Sha256, None).unwrap();
 
         assert_eq!(hex::encode(hash), expected_hash)
     }
+
+    #[test]
+    fn hash_known_answers() {
+        // digests of "abc"
+        let cases = [
+            (HashAlgorithm::Md5, "900150983cd24fb0d6963f7d28e17f72"),
+            (HashAlgorithm::Sha1, "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (
+                HashAlgorithm::Sha256,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                HashAlgorithm::Sha384,
+                "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7",
+            ),
+            (
+                HashAlgorithm::Sha512,
+                "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+            ),
+            (
+                HashAlgorithm::Sha3_256,
+                "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+            ),
+            (
+                HashAlgorithm::Sha3_512,
+                "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+            ),
+            (
+                HashAlgorithm::Blake2b,
+                "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+            ),
+            (
+                HashAlgorithm::Blake3,
+                "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+            ),
+        ];
+
+        for (algorithm, expected_hash) in cases {
+            let hash = hash_data("abc".as_bytes(), algorithm, None).unwrap();
+
+            assert_eq!(hash.len(), algorithm.output_size());
+            assert_eq!(hex::encode(hash), expected_hash, "{algorithm}");
+        }
+    }
+
+    #[test]
+    fn hash_file_streaming() {
+        let expected_hash = "cd6c8edf218a2c38d46d5b0ecf235a71185dc11f57735f607cdcc5e5339b2c3c";
+
+        let hash = hash_path("test-file-1.txt", HashAlgorithm::Sha256, None).unwrap();
+
+        assert_eq!(hex::encode(hash.digest), expected_hash);
+        assert_eq!(hash.size, 9);
+    }
+
+    #[test]
+    fn hash_file_streaming_failed() {
+        let error = hash_path("non-existent", HashAlgorithm::Sha256, None).unwrap_err();
+
+        assert_eq!(error.exit_code(), EXIT_NOT_FOUND);
+
+        let error = hash_path("src", HashAlgorithm::Sha256, None).unwrap_err();
+
+        assert_eq!(error.to_string(), "src: is a directory");
+    }
+
+    #[test]
+    fn hash_reader_across_chunks() {
+        // a few chunks plus a partial one, so chunk boundaries are exercised
+        let data: Vec<u8> = (0..CHUNK_SIZE * 3 + 17).map(|i| (i % 251) as u8).collect();
+
+        for algorithm in HashAlgorithm::ALL {
+            let streamed = hash_reader(Cursor::new(&data), Hasher::new(algorithm)).unwrap();
+
+            assert_eq!(streamed.size, data.len() as u64);
+            assert_eq!(
+                streamed.digest,
+                hash_data(&data, algorithm, None).unwrap(),
+                "{algorithm}"
+            );
+        }
+    }
+
+    #[test]
+    fn hash_files_keeps_order() {
+        let files: Vec<String> = ["test.txt", "non-existent", "test-file-1.txt"]
+            .iter()
+            .cycle()
+            .take(30)
+            .map(|file| file.to_string())
+            .collect();
+
+        let progress = Progress::default();
+        let text = TextNormalization::default();
+        let sequential =
+            hash_files(&files, HashAlgorithm::Sha256, None, 1, &Progress::default(), text);
+        let parallel = hash_files(&files, HashAlgorithm::Sha256, None, 4, &progress, text);
+
+        let digests = |results: Vec<Result<FileDigest, _>>| -> Vec<_> {
+            results.into_iter().map(|hash| hash.ok().map(|hash| hash.digest)).collect()
+        };
+        let sequential = digests(sequential);
+        let parallel = digests(parallel);
+        assert_eq!(parallel.len(), files.len());
+        assert_eq!(parallel, sequential);
+        assert_eq!(parallel[1], None);
+        // 10 times test.txt and test-file-1.txt
+        assert_eq!(progress.bytes(), 10 * (36 + 9));
+    }
+
+    #[test]
+    fn hmac_rfc4231_vectors() {
+        // (key, data, HMAC-SHA-256, HMAC-SHA-512), test case 5 is skipped as it checks
+        // truncated output
+        let cases = [
+            (
+                hex::decode("0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b").unwrap(),
+                b"Hi There".to_vec(),
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+                "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854",
+            ),
+            (
+                b"Jefe".to_vec(),
+                b"what do ya want for nothing?".to_vec(),
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+                "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+            ),
+            (
+                vec![0xaa; 20],
+                vec![0xdd; 50],
+                "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+                "fa73b0089d56a284efb0f0756c890be9b1b5dbdd8ee81a3655f83e33b2279d39bf3e848279a722c806b485a47e67c807b946a337bee8942674278859e13292fb",
+            ),
+            (
+                hex::decode("0102030405060708090a0b0c0d0e0f10111213141516171819").unwrap(),
+                vec![0xcd; 50],
+                "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+                "b0ba465637458c6990e5a8c5f61d4af7e576d97ff94b872de76f8050361ee3dba91ca5c11aa25eb4d679275cc5788063a5f19741120c4f2de2adebeb10a298dd",
+            ),
+            (
+                vec![0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First".to_vec(),
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+                "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f3526b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598",
+            ),
+            (
+                vec![0xaa; 131],
+                b"This is a test using a larger than block-size key and a larger than block-size data. The key needs to be hashed before being used by the HMAC algorithm.".to_vec(),
+                "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+                "e37b6a775dc87dbaa4dfa9f96e5e3ffddebd71f8867289865df5a32d20cdc944b6022cac3c4982b10d5eeb55c3e4de15134676fb6de0446065c97440fa8c6a58",
+            ),
+        ];
+
+        for (key, data, expected_sha256, expected_sha512) in cases {
+            let sha256 = hash_data(&data, HashAlgorithm::Sha256, Some(&key)).unwrap();
+            let sha512 = hash_data(&data, HashAlgorithm::Sha512, Some(&key)).unwrap();
+
+            assert_eq!(hex::encode(sha256), expected_sha256);
+            assert_eq!(hex::encode(sha512), expected_sha512);
+        }
+    }
+
+    #[test]
+    fn keyed_blake3() {
+        // official BLAKE3 test vector for an empty input
+        let key = b"whats the Elvish word for friend";
+        let expected_hash = "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26";
+
+        let hash = hash_data(&[], HashAlgorithm::Blake3, Some(key)).unwrap();
+
+        assert_eq!(hex::encode(hash), expected_hash);
+
+        let error = hash_data(&[], HashAlgorithm::Blake3, Some(b"short")).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "invalid key: BLAKE3 key must be exactly 32 bytes!"
+        );
+        assert_eq!(error.exit_code(), EXIT_USAGE);
+    }
+
+    #[test]
+    fn keyed_hash_file() {
+        let key = b"whats the Elvish word for friend".as_slice();
+        let content = read_file(&String::from("test-file-1.txt")).unwrap();
+
+        for algorithm in HashAlgorithm::ALL {
+            let streamed = hash_path("test-file-1.txt", algorithm, Some(key)).unwrap().digest;
+            let plain = hash_path("test-file-1.txt", algorithm, None).unwrap().digest;
+
+            assert_eq!(streamed, hash_data(&content, algorithm, Some(key)).unwrap());
+            assert_ne!(streamed, plain, "{algorithm}");
+        }
+    }
+
+    #[test]
+    fn load_inline_key() {
+        let key = KeySource::Inline(String::from("secret")).load().unwrap();
+
+        assert_eq!(key, b"secret");
+        assert_eq!(
+            KeySource::File(String::from("non-existent"))
+                .load()
+                .unwrap_err()
+                .exit_code(),
+            EXIT_NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn compare_digests() {
+        let hash = hash_path("test-file-1.txt", HashAlgorithm::Sha256, None).unwrap();
+        let expected =
+            hex::decode("cd6c8edf218a2c38d46d5b0ecf235a71185dc11f57735f607cdcc5e5339b2c3c")
+                .unwrap();
+        let mut tampered = expected.clone();
+        tampered[31] ^= 1;
+
+        assert!(digest_matches(&hash.digest, &expected));
+        assert!(!digest_matches(&hash.digest, &tampered));
+        assert!(!digest_matches(&hash.digest, &expected[..16]));
+    }
 }
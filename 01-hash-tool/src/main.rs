@@ -1,20 +1,407 @@
-use std::{env, process};
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::time::{Duration, Instant};
+use std::{env, fs, process};
 
-use rust_01_hash_tool::{Config, hash_data, read_file};
+use rust_01_hash_tool::bench::{run_bench, write_table};
+use rust_01_hash_tool::check::{parse_checksum_line, verify_entry, CheckStatus};
+use rust_01_hash_tool::dupes::{find_duplicates, write_groups, DupesOptions};
+use rust_01_hash_tool::encoding::decode_digest;
+use rust_01_hash_tool::manifest::{snapshot, DiffOptions, Manifest, ManifestDiff};
+use rust_01_hash_tool::merkle::{merkle_path, ChunkManifest, DEFAULT_CHUNK_SIZE};
+use rust_01_hash_tool::output::{write_records, HashRecord, OutputFormat};
+use rust_01_hash_tool::password::{hash_password, verify_password, PasswordCommand};
+use rust_01_hash_tool::progress::{format_bytes, throughput, Progress, ProgressBar};
+use rust_01_hash_tool::walk::collect_files;
+use rust_01_hash_tool::{
+    digest_matches, hash_files, new_hasher, write_completions, Command, Config, FileDigest,
+    HashAlgorithm, HashToolError, EXIT_MISMATCH, STDIN_PATH,
+};
 
 fn main() {
-    let config = Config::build(env::args()).unwrap_or_else(|e| {
-        eprintln!("Problem parsing arguments: {e}");
-        process::exit(1);
-    });
+    let exit_code = match run() {
+        Ok(exit_code) => exit_code,
+        // clap formats its own errors, and prints help and the version to stdout
+        Err(HashToolError::Cli(e)) => {
+            let _ = e.print();
+            e.exit_code()
+        }
+        Err(e) => {
+            eprintln!("hash-tool: {e}");
+            e.exit_code()
+        }
+    };
 
-    let file_content = read_file(&config.file_path).unwrap_or_else(|_e| {
-        eprintln!("Failed to load file '{}'", config.file_path);
-        process::exit(1);
-    });
+    process::exit(exit_code);
+}
+
+fn run() -> Result<i32, HashToolError> {
+    let config = match Command::build(env::args())? {
+        Command::Hash(config) => config,
+        Command::Password(command) => return password(command),
+        Command::Bench(options) => {
+            write_table(&mut io::stdout().lock(), &run_bench(&options))
+                .map_err(|e| HashToolError::io("<stdout>", e))?;
+            return Ok(0);
+        }
+        Command::Dupes(options) => return dupes(options),
+        Command::Snapshot(options) => {
+            let (manifest, failed) =
+                snapshot(&options.dir, options.algorithm, &options.walk, options.jobs)?;
+            options.write(&manifest)?;
+            return Ok(report_failed(&failed));
+        }
+        Command::Diff(options) => return diff(options),
+        Command::Completions(shell) => {
+            write_completions(shell, &mut io::stdout().lock());
+            return Ok(0);
+        }
+    };
+
+    let key = match &config.key {
+        Some(source) => Some(source.load()?),
+        None => None,
+    };
+    let key = key.as_deref();
+
+    // reject unusable keys and encodings up front instead of failing on every file
+    new_hasher(config.algorithm, key)?;
+    config.encoding.check_algorithm(config.algorithm)?;
+    if key.is_some() {
+        config.encoding.check_plain_digest("an HMAC")?;
+    }
+    if config.merkle {
+        config.encoding.check_plain_digest("a Merkle root")?;
+    }
+
+    if let Some(check_file) = &config.check_file {
+        return check(check_file, config.algorithm, key);
+    }
+
+    if let Some(expected) = &config.expect {
+        return expect(&config.file_paths[0], expected, &config, key);
+    }
+
+    let files = collect_files(&config.file_paths, &config.walk)?;
+
+    // when several files fail, the first failure decides the exit code
+    let mut exit_code = 0;
+    let mut records = Vec::with_capacity(files.len());
+    let results: Vec<_> = if let Some(fingerprint) = config.fingerprint {
+        files
+            .iter()
+            .map(|file_path| {
+                let started = Instant::now();
+                fingerprint
+                    .compute_path(file_path)
+                    .map(|(digest, size)| Hashed {
+                        digest,
+                        size,
+                        elapsed: started.elapsed(),
+                    })
+            })
+            .collect()
+    } else {
+        let results = if config.merkle {
+            merkle(&files, &config, key)?
+        } else {
+            hash_with_progress(&files, &config, key)
+        };
+        results
+            .into_iter()
+            .map(|result| {
+                result.map(|hash| Hashed {
+                    digest: config.encoding.encode(&hash.digest, config.algorithm),
+                    size: hash.size,
+                    elapsed: hash.elapsed,
+                })
+            })
+            .collect()
+    };
+    let algorithm = match config.fingerprint {
+        Some(fingerprint) => fingerprint.name().to_string(),
+        None => config.algorithm.to_string(),
+    };
+
+    for (file_path, result) in files.into_iter().zip(results) {
+        let mut record = HashRecord {
+            path: file_path,
+            algorithm: algorithm.clone(),
+            digest: None,
+            size: None,
+            error: None,
+        };
+
+        match result {
+            Ok(hashed) => {
+                if config.stats {
+                    eprintln!(
+                        "{}: {} bytes in {:.3}s ({}/s)",
+                        record.path,
+                        hashed.size,
+                        hashed.elapsed.as_secs_f64(),
+                        format_bytes(throughput(hashed.size, hashed.elapsed) as u64)
+                    );
+                }
+                record.digest = Some(hashed.digest);
+                record.size = Some(hashed.size);
+            }
+            Err(e) => {
+                if config.format != OutputFormat::Json {
+                    eprintln!("hash-tool: {e}");
+                }
+                if exit_code == 0 {
+                    exit_code = e.exit_code();
+                }
+                record.error = Some(e.to_string());
+            }
+        }
+        records.push(record);
+    }
+
+    write_records(&mut io::stdout().lock(), &records, config.format)
+        .map_err(|e| HashToolError::io("<stdout>", e))?;
+
+    Ok(exit_code)
+}
+
+/// Encoded digest or fingerprint of a file.
+struct Hashed {
+    digest: String,
+    size: u64,
+    elapsed: Duration,
+}
+
+/// `hash_files` with a progress bar on stderr, unless stdout is redirected or it's turned off.
+fn hash_with_progress(
+    files: &[String],
+    config: &Config,
+    key: Option<&[u8]>,
+) -> Vec<Result<FileDigest, HashToolError>> {
+    let progress = Progress::default();
+    if config.no_progress || !io::stdout().is_terminal() {
+        return hash_files(
+            files,
+            config.algorithm,
+            key,
+            config.jobs,
+            &progress,
+            config.text,
+        );
+    }
+
+    // the size of the standard input isn't known up front
+    let total = files
+        .iter()
+        .map(|file_path| match file_path.as_str() {
+            STDIN_PATH => None,
+            _ => Some(fs::metadata(file_path).map_or(0, |metadata| metadata.len())),
+        })
+        .sum();
+
+    let bar = ProgressBar::start(progress.clone(), total);
+    let results = hash_files(
+        files,
+        config.algorithm,
+        key,
+        config.jobs,
+        &progress,
+        config.text,
+    );
+    bar.finish();
+
+    results
+}
+
+/// Verifies every entry of a checksum file the way `sha256sum --check` does and returns the
+/// process exit code.
+fn check(
+    check_file: &str,
+    algorithm: HashAlgorithm,
+    key: Option<&[u8]>,
+) -> Result<i32, HashToolError> {
+    let content = if check_file == STDIN_PATH {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content).map(|_| content)
+    } else {
+        fs::read_to_string(check_file)
+    };
+    let content = content.map_err(|e| HashToolError::io(check_file, e))?;
+
+    let mut out = io::stdout().lock();
+    let mut checked = 0;
+    let mut improperly_formatted = 0;
+    let mut failed = 0;
+    let mut failed_read = 0;
+
+    for line in content.lines() {
+        let entry = match parse_checksum_line(line) {
+            Some(entry) => entry,
+            None => {
+                if !line.trim().is_empty() && !line.starts_with('#') {
+                    improperly_formatted += 1;
+                }
+                continue;
+            }
+        };
+
+        checked += 1;
+        let status = match verify_entry(&entry, algorithm, key) {
+            CheckStatus::Ok => "OK",
+            CheckStatus::Failed => {
+                failed += 1;
+                "FAILED"
+            }
+            CheckStatus::ReadFailed => {
+                failed_read += 1;
+                "FAILED open or read"
+            }
+        };
+        writeln!(out, "{}: {status}", entry.path).map_err(|e| HashToolError::io("<stdout>", e))?;
+    }
+
+    if improperly_formatted > 0 {
+        eprintln!("WARNING: {improperly_formatted} line(s) are improperly formatted");
+    }
+    if failed_read > 0 {
+        eprintln!("WARNING: {failed_read} listed file(s) could not be read");
+    }
+    if failed > 0 {
+        eprintln!("WARNING: {failed} computed checksum(s) did NOT match");
+    }
+
+    if checked == 0 {
+        return Err(HashToolError::Usage(format!(
+            "{check_file}: no properly formatted checksum lines found"
+        )));
+    }
+
+    if failed > 0 || failed_read > 0 {
+        Ok(EXIT_MISMATCH)
+    } else {
+        Ok(0)
+    }
+}
+
+/// Hashes a single file and compares it with the expected digest, returns the process exit code.
+fn expect(
+    file_path: &str,
+    expected: &str,
+    config: &Config,
+    key: Option<&[u8]>,
+) -> Result<i32, HashToolError> {
+    let expected = decode_digest(expected, config.algorithm)?;
+    let files = [file_path.to_string()];
+    let progress = Progress::default();
+    let hash = hash_files(&files, config.algorithm, key, 1, &progress, config.text).remove(0)?;
+
+    let mut out = io::stdout().lock();
+    if digest_matches(&hash.digest, &expected) {
+        writeln!(out, "{file_path}: OK").map_err(|e| HashToolError::io("<stdout>", e))?;
+        Ok(0)
+    } else {
+        writeln!(
+            out,
+            "{file_path}: FAILED, expected {} but got {}",
+            config.encoding.encode(&expected, config.algorithm),
+            config.encoding.encode(&hash.digest, config.algorithm)
+        )
+        .map_err(|e| HashToolError::io("<stdout>", e))?;
+        Ok(EXIT_MISMATCH)
+    }
+}
+
+/// Digests every file as a Merkle root and writes the chunk manifest if asked to.
+fn merkle(
+    files: &[String],
+    config: &Config,
+    key: Option<&[u8]>,
+) -> Result<Vec<Result<FileDigest, HashToolError>>, HashToolError> {
+    let chunk_size = match config.chunk_size {
+        0 => DEFAULT_CHUNK_SIZE,
+        n => n,
+    };
+    if config.chunk_manifest.is_some() && files.len() != 1 {
+        return Err(HashToolError::Usage(String::from(
+            "--chunk-manifest needs exactly one file",
+        )));
+    }
+
+    let mut results = Vec::with_capacity(files.len());
+    for file_path in files {
+        let started = Instant::now();
+        let tree = merkle_path(file_path, chunk_size, config.algorithm, key);
+
+        if let (Ok(tree), Some(manifest_path)) = (&tree, &config.chunk_manifest) {
+            ChunkManifest::new(tree, chunk_size, config.algorithm).write(manifest_path)?;
+        }
+        results.push(tree.map(|tree| FileDigest {
+            digest: tree.root,
+            size: tree.size,
+            elapsed: started.elapsed(),
+        }));
+    }
+
+    Ok(results)
+}
+
+/// Prints groups of identical files, unreadable files are reported but don't stop the search.
+fn dupes(options: DupesOptions) -> Result<i32, HashToolError> {
+    let files = collect_files(&options.paths, &options.walk)?;
+    let (groups, errors) = find_duplicates(&files, options.algorithm, options.jobs);
+
+    for e in &errors {
+        eprintln!("hash-tool: {e}");
+    }
+    write_groups(&mut io::stdout().lock(), &groups, options.format)
+        .map_err(|e| HashToolError::io("<stdout>", e))?;
+
+    Ok(errors.first().map_or(0, |e| e.exit_code()))
+}
+
+/// Compares a directory with its manifest, exits with `EXIT_MISMATCH` when anything changed.
+fn diff(options: DiffOptions) -> Result<i32, HashToolError> {
+    let old = Manifest::read(&options.manifest)?;
+    let algorithm: HashAlgorithm = old.algorithm.parse()?;
+    let (new, failed) = snapshot(&options.dir, algorithm, &options.walk, options.jobs)?;
+
+    let exit_code = report_failed(&failed);
+    let unreadable: Vec<_> = failed.into_iter().map(|(path, _e)| path).collect();
+    let diff = ManifestDiff::between(&old, &new, &unreadable);
+    diff.write(&mut io::stdout().lock(), options.format)
+        .map_err(|e| HashToolError::io("<stdout>", e))?;
+
+    match (exit_code, diff.is_empty()) {
+        (0, false) => Ok(EXIT_MISMATCH),
+        (exit_code, _) => Ok(exit_code),
+    }
+}
+
+/// Prints files which couldn't be hashed, the first one decides the exit code.
+fn report_failed(failed: &[(String, HashToolError)]) -> i32 {
+    for (_path, e) in failed {
+        eprintln!("hash-tool: {e}");
+    }
+
+    failed.first().map_or(0, |(_path, e)| e.exit_code())
+}
+
+/// Hashes or verifies the password from the first line of the standard input.
+fn password(command: PasswordCommand) -> Result<i32, HashToolError> {
+    let mut password = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut password)
+        .map_err(|e| HashToolError::io(STDIN_PATH, e))?;
+    let password = password.trim_end_matches(['\n', '\r']).as_bytes();
 
-    let hash = hash_data(file_content);
+    let (line, exit_code) = match command {
+        PasswordCommand::Hash(params) => (hash_password(password, &params)?, 0),
+        PasswordCommand::Verify(hash) => match verify_password(password, &hash)? {
+            true => (String::from("OK"), 0),
+            false => (String::from("FAILED"), EXIT_MISMATCH),
+        },
+    };
+    writeln!(io::stdout().lock(), "{line}").map_err(|e| HashToolError::io("<stdout>", e))?;
 
-    println!("\nHashing file: {}", config.file_path);
-    println!("File SHA-256 hash: {}", hex::encode(hash));
+    Ok(exit_code)
 }
@@ -0,0 +1,311 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::normalize::TextNormalization;
+use crate::output::OutputFormat;
+use crate::progress::Progress;
+use crate::walk::{collect_files, WalkOptions};
+use crate::{hash_files, HashAlgorithm, HashToolError, STDIN_PATH};
+
+/// State of a directory tree at the time of the snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub algorithm: String,
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Relative to the snapshot directory, with `/` separators on every platform.
+    pub path: String,
+    pub size: u64,
+    /// Modification time in seconds since the Unix epoch.
+    pub mtime: u64,
+    /// Hex encoded.
+    pub digest: String,
+}
+
+impl Manifest {
+    pub fn read(path: &str) -> Result<Manifest, HashToolError> {
+        let file = File::open(path).map_err(|e| HashToolError::io(path, e))?;
+
+        serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| HashToolError::Usage(format!("{path}: invalid manifest: {e}")))
+    }
+
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut *out, self)?;
+        writeln!(out)
+    }
+}
+
+/// Hashes every file below `dir`, which has to be a directory. Files which can't be read are left
+/// out of the manifest and returned with their relative path.
+pub fn snapshot(
+    dir: &str,
+    algorithm: HashAlgorithm,
+    walk: &WalkOptions,
+    jobs: usize,
+) -> Result<(Manifest, Vec<(String, HashToolError)>), HashToolError> {
+    // paths are relative to `dir`, a single file or the standard input would have an empty one
+    let is_dir = match dir {
+        STDIN_PATH => false,
+        _ => fs::metadata(dir)
+            .map_err(|e| HashToolError::io(dir, e))?
+            .is_dir(),
+    };
+    if !is_dir {
+        return Err(HashToolError::Usage(format!("{dir}: is not a directory")));
+    }
+
+    let walk = WalkOptions {
+        recursive: true,
+        ..walk.clone()
+    };
+    let files = collect_files(&[dir.to_string()], &walk)?;
+    let results = hash_files(
+        &files,
+        algorithm,
+        None,
+        jobs,
+        &Progress::default(),
+        TextNormalization::default(),
+    );
+
+    let mut entries = vec![];
+    let mut failed = vec![];
+    for (file_path, result) in files.iter().zip(results) {
+        let path = relative_path(dir, file_path);
+        let metadata = result.and_then(|hash| {
+            fs::metadata(file_path)
+                .map(|metadata| (hash, metadata))
+                .map_err(|e| HashToolError::io(file_path, e))
+        });
+
+        match metadata {
+            Ok((hash, metadata)) => entries.push(ManifestEntry {
+                path,
+                size: hash.size,
+                mtime: metadata
+                    .modified()
+                    .ok()
+                    .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+                    .map_or(0, |mtime| mtime.as_secs()),
+                digest: hex::encode(hash.digest),
+            }),
+            Err(e) => failed.push((path, e)),
+        }
+    }
+
+    let manifest = Manifest {
+        algorithm: algorithm.to_string(),
+        entries,
+    };
+    Ok((manifest, failed))
+}
+
+fn relative_path(dir: &str, file_path: &str) -> String {
+    Path::new(file_path)
+        .strip_prefix(dir)
+        .unwrap_or(Path::new(file_path))
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Paths which differ between two manifests, each list sorted.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ManifestDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Content changed, a new modification time alone doesn't count.
+    pub modified: Vec<String>,
+}
+
+impl ManifestDiff {
+    /// `unreadable` paths exist but couldn't be hashed now, so they are neither removed nor added.
+    pub fn between(old: &Manifest, new: &Manifest, unreadable: &[String]) -> ManifestDiff {
+        let old: BTreeMap<_, _> = old.entries.iter().map(|e| (&e.path, e)).collect();
+        let new: BTreeMap<_, _> = new.entries.iter().map(|e| (&e.path, e)).collect();
+        let mut diff = ManifestDiff::default();
+
+        for (path, entry) in &old {
+            match new.get(path) {
+                Some(current) if current.size != entry.size || current.digest != entry.digest => {
+                    diff.modified.push(path.to_string())
+                }
+                Some(_current) => {}
+                None if unreadable.contains(path) => {}
+                None => diff.removed.push(path.to_string()),
+            }
+        }
+        diff.added = new
+            .keys()
+            .filter(|path| !old.contains_key(*path))
+            .map(|path| path.to_string())
+            .collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    /// Text output has one line per path, prefixed with `A`, `D` or `M` like `git diff
+    /// --name-status`.
+    pub fn write(&self, out: &mut impl Write, format: OutputFormat) -> io::Result<()> {
+        if format == OutputFormat::Json {
+            serde_json::to_writer_pretty(&mut *out, self)?;
+            return writeln!(out);
+        }
+
+        for (status, paths) in [
+            ("A", &self.added),
+            ("D", &self.removed),
+            ("M", &self.modified),
+        ] {
+            for path in paths {
+                writeln!(out, "{status}\t{path}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// `hash-tool snapshot <dir>`, the manifest goes to stdout unless `output` is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotOptions {
+    pub dir: String,
+    pub output: Option<String>,
+    pub algorithm: HashAlgorithm,
+    pub walk: WalkOptions,
+    pub jobs: usize,
+}
+
+impl SnapshotOptions {
+    /// Writes the manifest where it was asked for.
+    pub fn write(&self, manifest: &Manifest) -> Result<(), HashToolError> {
+        match &self.output {
+            Some(path) => {
+                let file = File::create(path).map_err(|e| HashToolError::io(path, e))?;
+                let mut out = BufWriter::new(file);
+                manifest
+                    .write(&mut out)
+                    .and_then(|_| out.flush())
+                    .map_err(|e| HashToolError::io(path, e))
+            }
+            None => manifest
+                .write(&mut io::stdout().lock())
+                .map_err(|e| HashToolError::io("<stdout>", e)),
+        }
+    }
+}
+
+/// `hash-tool diff <manifest> <dir>`, the directory is hashed with the manifest's algorithm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffOptions {
+    pub manifest: String,
+    pub dir: String,
+    pub walk: WalkOptions,
+    pub jobs: usize,
+    pub format: OutputFormat,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::manifest::{snapshot, Manifest, ManifestDiff};
+    use crate::output::OutputFormat;
+    use crate::test_dir::TestDir;
+    use crate::walk::WalkOptions;
+    use crate::HashAlgorithm;
+
+    fn prepare_tree(name: &str) -> TestDir {
+        let root = TestDir::new(name);
+        root.write("app.bin", "v1");
+        root.write("etc/app.conf", "port = 80");
+        root.write("README", "hello");
+
+        root
+    }
+
+    #[test]
+    fn snapshot_and_diff() {
+        let root = prepare_tree("manifest");
+        let dir = &root.path_string();
+        let (before, failed) =
+            snapshot(dir, HashAlgorithm::Sha256, &WalkOptions::default(), 2).unwrap();
+
+        // round trip through JSON, like a manifest kept on disk
+        let mut json = vec![];
+        before.write(&mut json).unwrap();
+        let before: Manifest = serde_json::from_slice(&json).unwrap();
+
+        fs::write(root.join("etc/app.conf"), "port = 8080").unwrap();
+        fs::remove_file(root.join("README")).unwrap();
+        fs::write(root.join("etc/extra.conf"), "").unwrap();
+        let (after, _failed) =
+            snapshot(dir, HashAlgorithm::Sha256, &WalkOptions::default(), 2).unwrap();
+
+        let diff = ManifestDiff::between(&before, &after, &[]);
+        let mut text = vec![];
+        diff.write(&mut text, OutputFormat::Text).unwrap();
+
+        assert!(failed.is_empty());
+        assert_eq!(
+            before
+                .entries
+                .iter()
+                .map(|e| e.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["README", "app.bin", "etc/app.conf"]
+        );
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "A\tetc/extra.conf\nD\tREADME\nM\tetc/app.conf\n"
+        );
+        assert!(ManifestDiff::between(&after, &after, &[]).is_empty());
+    }
+
+    #[test]
+    fn unreadable_file_is_not_removed() {
+        let root = prepare_tree("manifest-unreadable");
+        let dir = &root.path_string();
+        let (before, _failed) =
+            snapshot(dir, HashAlgorithm::Sha256, &WalkOptions::default(), 1).unwrap();
+        let mut after = before.clone();
+        after.entries.retain(|e| e.path != "README");
+
+        assert_eq!(
+            ManifestDiff::between(&before, &after, &[String::from("README")]),
+            ManifestDiff::default()
+        );
+    }
+
+    #[test]
+    fn snapshot_only_directories() {
+        let root = prepare_tree("manifest-not-dir");
+        let snapshot_of = |path: &str| {
+            snapshot(path, HashAlgorithm::Sha256, &WalkOptions::default(), 1).unwrap_err()
+        };
+
+        let file = root.join("README").to_string_lossy().into_owned();
+        assert_eq!(
+            snapshot_of(&file).to_string(),
+            format!("{file}: is not a directory")
+        );
+        assert_eq!(snapshot_of("-").to_string(), "-: is not a directory");
+        assert_eq!(snapshot_of("-").exit_code(), crate::EXIT_USAGE);
+        assert_eq!(
+            snapshot_of(&root.join("missing").to_string_lossy()).exit_code(),
+            crate::EXIT_NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    digest_matches, hash_reader, new_hasher, Digester, HashAlgorithm, HashToolError, STDIN_PATH,
+};
+
+pub const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Prepended to a chunk before hashing it into a leaf.
+const LEAF_PREFIX: u8 = 0x00;
+/// Prepended to two children before hashing them into their parent, so a node can't pass for a
+/// leaf and a file made of digests doesn't share the root of the file they were made from.
+const NODE_PREFIX: u8 = 0x01;
+
+/// Binary hash tree over fixed-size chunks of a file, hashed like RFC 6962 does: leaves are the
+/// digests of `0x00 || chunk` and each parent the digest of `0x01 || left || right`. An odd node
+/// at the end of a level is moved up unchanged instead of being paired with itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerkleTree {
+    pub root: Vec<u8>,
+    pub chunks: Vec<Vec<u8>>,
+    pub size: u64,
+}
+
+impl MerkleTree {
+    /// An empty input still has one (empty) chunk, so its root is the leaf digest of no data.
+    pub fn build(
+        data: &[u8],
+        chunk_size: usize,
+        algorithm: HashAlgorithm,
+        key: Option<&[u8]>,
+    ) -> Result<MerkleTree, HashToolError> {
+        MerkleTree::from_reader(data, chunk_size, algorithm, key, "<data>")
+    }
+
+    /// Hashes the chunks as they are read, so only one at a time is held in memory. `file_path`
+    /// names the input in errors.
+    pub fn from_reader(
+        mut reader: impl Read,
+        chunk_size: usize,
+        algorithm: HashAlgorithm,
+        key: Option<&[u8]>,
+        file_path: &str,
+    ) -> Result<MerkleTree, HashToolError> {
+        if chunk_size == 0 {
+            return Err(HashToolError::Usage(String::from(
+                "chunk size must be greater than zero",
+            )));
+        }
+
+        let mut chunks = vec![];
+        let mut size = 0;
+        loop {
+            let chunk = (&mut reader).take(chunk_size as u64);
+            let mut hasher = new_hasher(algorithm, key)?;
+            hasher.update(&[LEAF_PREFIX]);
+            let hash = hash_reader(chunk, hasher).map_err(|e| HashToolError::io(file_path, e))?;
+            // the end of the input, unless it's empty
+            if hash.size == 0 && !chunks.is_empty() {
+                break;
+            }
+
+            size += hash.size;
+            chunks.push(hash.digest);
+            if hash.size < chunk_size as u64 {
+                break;
+            }
+        }
+
+        Ok(MerkleTree {
+            root: merkle_root(&chunks, algorithm, key)?,
+            chunks,
+            size,
+        })
+    }
+}
+
+/// Builds the tree for a file, streamed like `hash_path` does, `-` reads from the standard input.
+pub fn merkle_path(
+    file_path: &str,
+    chunk_size: usize,
+    algorithm: HashAlgorithm,
+    key: Option<&[u8]>,
+) -> Result<MerkleTree, HashToolError> {
+    if file_path == STDIN_PATH {
+        return MerkleTree::from_reader(io::stdin().lock(), chunk_size, algorithm, key, file_path);
+    }
+
+    let file = File::open(file_path).map_err(|e| HashToolError::io(file_path, e))?;
+    MerkleTree::from_reader(file, chunk_size, algorithm, key, file_path)
+}
+
+/// Leaf digest of a single chunk.
+pub fn hash_leaf(
+    chunk: &[u8],
+    algorithm: HashAlgorithm,
+    key: Option<&[u8]>,
+) -> Result<Vec<u8>, HashToolError> {
+    let mut hasher = new_hasher(algorithm, key)?;
+    hasher.update(&[LEAF_PREFIX]);
+    hasher.update(chunk);
+    Ok(hasher.finalize())
+}
+
+fn hash_node(
+    left: &[u8],
+    right: &[u8],
+    algorithm: HashAlgorithm,
+    key: Option<&[u8]>,
+) -> Result<Vec<u8>, HashToolError> {
+    let mut hasher = new_hasher(algorithm, key)?;
+    hasher.update(&[NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    Ok(hasher.finalize())
+}
+
+/// Folds leaf digests level by level up to the root.
+pub fn merkle_root(
+    leaves: &[Vec<u8>],
+    algorithm: HashAlgorithm,
+    key: Option<&[u8]>,
+) -> Result<Vec<u8>, HashToolError> {
+    let mut level = leaves.to_vec();
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => hash_node(left, right, algorithm, key),
+                [odd] => Ok(odd.clone()),
+                _ => unreachable!(),
+            })
+            .collect::<Result<_, _>>()?;
+    }
+
+    level
+        .pop()
+        .ok_or_else(|| HashToolError::Usage(String::from("Merkle tree needs at least one chunk")))
+}
+
+/// Everything needed to verify single chunks of a file later on, e.g. after a resumed transfer.
+/// Digests are hex encoded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChunkManifest {
+    pub algorithm: String,
+    pub chunk_size: usize,
+    pub size: u64,
+    pub root: String,
+    pub chunks: Vec<String>,
+}
+
+impl ChunkManifest {
+    pub fn new(tree: &MerkleTree, chunk_size: usize, algorithm: HashAlgorithm) -> ChunkManifest {
+        ChunkManifest {
+            algorithm: algorithm.to_string(),
+            chunk_size,
+            size: tree.size,
+            root: hex::encode(&tree.root),
+            chunks: tree.chunks.iter().map(hex::encode).collect(),
+        }
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), HashToolError> {
+        let mut out = BufWriter::new(File::create(path).map_err(|e| HashToolError::io(path, e))?);
+
+        serde_json::to_writer_pretty(&mut out, self)
+            .map_err(|e| HashToolError::io(path, e.into()))?;
+        writeln!(out)
+            .and_then(|_| out.flush())
+            .map_err(|e| HashToolError::io(path, e))
+    }
+
+    /// Checks chunk `index` against the manifest, after making sure the listed chunk digests
+    /// really add up to the root.
+    pub fn verify_chunk(
+        &self,
+        index: usize,
+        chunk: &[u8],
+        key: Option<&[u8]>,
+    ) -> Result<bool, HashToolError> {
+        let algorithm: HashAlgorithm = self.algorithm.parse()?;
+        let invalid = || HashToolError::Usage(String::from("chunk manifest is not valid hex"));
+
+        let chunks = self
+            .chunks
+            .iter()
+            .map(|digest| hex::decode(digest).map_err(|_e| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let root = hex::decode(&self.root).map_err(|_e| invalid())?;
+        if !digest_matches(&merkle_root(&chunks, algorithm, key)?, &root) {
+            return Ok(false);
+        }
+
+        match chunks.get(index) {
+            Some(expected) => Ok(digest_matches(&hash_leaf(chunk, algorithm, key)?, expected)),
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::merkle::{merkle_path, ChunkManifest, MerkleTree};
+    use crate::{hash_data, HashAlgorithm};
+
+    fn sha256(data: &[u8]) -> Vec<u8> {
+        hash_data(data, HashAlgorithm::Sha256, None).unwrap()
+    }
+
+    fn leaf(chunk: &[u8]) -> Vec<u8> {
+        sha256(&[b"\x00", chunk].concat())
+    }
+
+    fn node(left: &[u8], right: &[u8]) -> Vec<u8> {
+        sha256(&[b"\x01", left, right].concat())
+    }
+
+    #[test]
+    fn root_with_odd_chunk_count() {
+        let tree = MerkleTree::build(b"aabbc", 2, HashAlgorithm::Sha256, None).unwrap();
+
+        let ab = node(&leaf(b"aa"), &leaf(b"bb"));
+        assert_eq!(tree.chunks, vec![leaf(b"aa"), leaf(b"bb"), leaf(b"c")]);
+        assert_eq!(tree.root, node(&ab, &leaf(b"c")));
+        assert_eq!(tree.size, 5);
+    }
+
+    #[test]
+    fn single_chunk_and_empty_file() {
+        let single = MerkleTree::build(b"abc", 4, HashAlgorithm::Sha256, None).unwrap();
+        let empty = MerkleTree::build(b"", 4, HashAlgorithm::Sha256, None).unwrap();
+
+        assert_eq!(single.root, leaf(b"abc"));
+        assert_eq!(empty.root, leaf(b""));
+        assert!(MerkleTree::build(b"abc", 0, HashAlgorithm::Sha256, None).is_err());
+    }
+
+    #[test]
+    fn no_empty_chunk_after_full_ones() {
+        let tree = MerkleTree::build(b"aabb", 2, HashAlgorithm::Sha256, None).unwrap();
+
+        assert_eq!(tree.chunks, vec![leaf(b"aa"), leaf(b"bb")]);
+        assert_eq!(tree.size, 4);
+    }
+
+    #[test]
+    fn stream_file_chunks() {
+        let content = std::fs::read("test-file-1.txt").unwrap();
+
+        let tree = merkle_path("test-file-1.txt", 4, HashAlgorithm::Sha256, None).unwrap();
+
+        assert_eq!(
+            tree,
+            MerkleTree::build(&content, 4, HashAlgorithm::Sha256, None).unwrap()
+        );
+        assert_eq!(tree.chunks[0], leaf(&content[..4]));
+        assert!(merkle_path("no-such-file", 4, HashAlgorithm::Sha256, None).is_err());
+    }
+
+    #[test]
+    fn verify_chunks_with_manifest() {
+        let tree = merkle_path("test-file-1.txt", 4, HashAlgorithm::Sha256, None).unwrap();
+        let manifest = ChunkManifest::new(&tree, 4, HashAlgorithm::Sha256);
+        let content = std::fs::read("test-file-1.txt").unwrap();
+
+        assert_eq!(manifest.chunks.len(), 3);
+        assert!(manifest.verify_chunk(1, &content[4..8], None).unwrap());
+        assert!(!manifest.verify_chunk(1, &content[0..4], None).unwrap());
+        assert!(!manifest.verify_chunk(3, b"", None).unwrap());
+
+        let mut tampered = manifest.clone();
+        tampered.chunks[1] = hex::encode(leaf(b"evil"));
+        assert!(!tampered.verify_chunk(1, b"evil", None).unwrap());
+    }
+
+    #[test]
+    fn digests_are_not_chunks() {
+        let content = [[b'x'; 64], [b'y'; 64]].concat();
+        let digests = [sha256(&[b'x'; 64]), sha256(&[b'y'; 64])].concat();
+
+        let tree = MerkleTree::build(&content, 64, HashAlgorithm::Sha256, None).unwrap();
+        let forged = MerkleTree::build(&digests, 32, HashAlgorithm::Sha256, None).unwrap();
+        let forged_at_64 = MerkleTree::build(&digests, 64, HashAlgorithm::Sha256, None).unwrap();
+
+        assert_ne!(tree.root, forged.root);
+        assert_ne!(tree.root, forged_at_64.root);
+    }
+}
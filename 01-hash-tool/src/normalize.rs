@@ -0,0 +1,250 @@
+use std::io::{self, BufRead, Read};
+use std::str::FromStr;
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::HashToolError;
+
+const BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Rewrites applied to text before hashing, so copies which differ only in how they were saved
+/// hash the same. All off by default.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TextNormalization {
+    /// CRLF line endings become LF.
+    pub crlf: bool,
+    /// Spaces and tabs at the end of a line are dropped.
+    pub trailing_whitespace: bool,
+    /// A UTF-8 byte order mark at the start is dropped.
+    pub bom: bool,
+    /// Lines are brought to Unicode NFC, lines which aren't UTF-8 are kept as they are.
+    pub nfc: bool,
+    /// A missing newline at the end is added.
+    pub final_newline: bool,
+}
+
+impl TextNormalization {
+    pub fn all() -> Self {
+        TextNormalization {
+            crlf: true,
+            trailing_whitespace: true,
+            bom: true,
+            nfc: true,
+            final_newline: true,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        *self != TextNormalization::default()
+    }
+}
+
+impl FromStr for TextNormalization {
+    type Err = HashToolError;
+
+    /// Comma separated names, e.g. `crlf,bom`, or `all`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut normalization = TextNormalization::default();
+
+        for name in s.split(',') {
+            match name.trim() {
+                "all" => normalization = TextNormalization::all(),
+                "crlf" => normalization.crlf = true,
+                "trailing-whitespace" => normalization.trailing_whitespace = true,
+                "bom" => normalization.bom = true,
+                "nfc" => normalization.nfc = true,
+                "final-newline" => normalization.final_newline = true,
+                _ => {
+                    return Err(HashToolError::Usage(format!(
+                        "unknown text normalization '{name}'"
+                    )))
+                }
+            }
+        }
+
+        Ok(normalization)
+    }
+}
+
+/// Applies a `TextNormalization` while reading, one line at a time, so only the longest line has
+/// to fit in memory.
+pub struct NormalizingReader<R> {
+    reader: R,
+    normalization: TextNormalization,
+    /// Normalized bytes not handed out yet.
+    pending: Vec<u8>,
+    position: usize,
+    first_line: bool,
+    done: bool,
+}
+
+impl<R: BufRead> NormalizingReader<R> {
+    pub fn new(reader: R, normalization: TextNormalization) -> Self {
+        NormalizingReader {
+            reader,
+            normalization,
+            pending: vec![],
+            position: 0,
+            first_line: true,
+            done: false,
+        }
+    }
+
+    /// Reads and normalizes the next line into `pending`, returns false at the end of input.
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut raw = vec![];
+        self.pending.clear();
+        self.position = 0;
+
+        if self.done || self.reader.read_until(b'\n', &mut raw)? == 0 {
+            self.done = true;
+            return Ok(false);
+        }
+
+        let mut line = &raw[..];
+        if self.first_line && self.normalization.bom {
+            line = line.strip_prefix(BOM).unwrap_or(line);
+        }
+        self.first_line = false;
+
+        let (mut content, mut ending): (&[u8], &[u8]) = match line {
+            [content @ .., b'\r', b'\n'] => (content, b"\r\n"),
+            [content @ .., b'\n'] => (content, b"\n"),
+            // a last line without a newline is followed by the end of the input
+            content => (content, b""),
+        };
+        if self.normalization.crlf && ending == b"\r\n" {
+            ending = b"\n";
+        }
+        if self.normalization.final_newline && ending.is_empty() {
+            ending = b"\n";
+        }
+        if self.normalization.trailing_whitespace {
+            let end = content
+                .iter()
+                .rposition(|&byte| byte != b' ' && byte != b'\t')
+                .map_or(0, |i| i + 1);
+            content = &content[..end];
+        }
+
+        match std::str::from_utf8(content) {
+            Ok(text) if self.normalization.nfc => {
+                self.pending.extend(text.nfc().collect::<String>().bytes())
+            }
+            _ => self.pending.extend_from_slice(content),
+        }
+        self.pending.extend_from_slice(ending);
+
+        Ok(true)
+    }
+}
+
+impl<R: BufRead> Read for NormalizingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.pending.len() {
+            if !self.fill()? {
+                return Ok(0);
+            }
+        }
+
+        let read = buf.len().min(self.pending.len() - self.position);
+        buf[..read].copy_from_slice(&self.pending[self.position..self.position + read]);
+        self.position += read;
+        Ok(read)
+    }
+}
+
+/// Normalizes text held in memory, e.g. before `hash_data`.
+pub fn normalize_text(data: &[u8], normalization: TextNormalization) -> Vec<u8> {
+    let mut normalized = Vec::with_capacity(data.len());
+    NormalizingReader::new(data, normalization)
+        .read_to_end(&mut normalized)
+        .expect("reading from memory doesn't fail");
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use crate::normalize::{normalize_text, NormalizingReader, TextNormalization};
+    use crate::read_file;
+
+    #[test]
+    fn parse_normalization_names() {
+        assert_eq!(
+            "crlf, trailing-whitespace"
+                .parse::<TextNormalization>()
+                .unwrap(),
+            TextNormalization {
+                crlf: true,
+                trailing_whitespace: true,
+                ..TextNormalization::default()
+            }
+        );
+        assert_eq!(
+            "all".parse::<TextNormalization>().unwrap(),
+            TextNormalization::all()
+        );
+        assert!("tabs".parse::<TextNormalization>().is_err());
+    }
+
+    #[test]
+    fn normalize_each_rewrite() {
+        let only = |name: &str| name.parse::<TextNormalization>().unwrap();
+
+        assert_eq!(normalize_text(b"a\r\nb\r\n", only("crlf")), b"a\nb\n");
+        assert_eq!(
+            normalize_text(b"a \t\r\nb  ", only("trailing-whitespace")),
+            b"a\r\nb"
+        );
+        assert_eq!(
+            normalize_text(b"\xef\xbb\xbfa\n\xef\xbb\xbf", only("bom")),
+            b"a\n\xef\xbb\xbf"
+        );
+        assert_eq!(
+            normalize_text("e\u{301}\n".as_bytes(), only("nfc")),
+            "\u{e9}\n".as_bytes()
+        );
+        assert_eq!(normalize_text(b"a\nb", only("final-newline")), b"a\nb\n");
+        assert_eq!(normalize_text(b"", only("final-newline")), b"");
+    }
+
+    #[test]
+    fn same_text_same_bytes() {
+        let original = read_file(&String::from("test-file-1.txt")).unwrap();
+        let edited = "\u{feff}test-123  \r\n".as_bytes();
+
+        assert_eq!(normalize_text(edited, TextNormalization::all()), original);
+        assert_eq!(
+            normalize_text(b"test-123", TextNormalization::all()),
+            original
+        );
+    }
+
+    #[test]
+    fn keep_invalid_utf8() {
+        let data = b"\xff\xfe e\xcc\x81\r\n";
+
+        assert_eq!(
+            normalize_text(data, TextNormalization::all()),
+            b"\xff\xfe e\xcc\x81\n"
+        );
+    }
+
+    #[test]
+    fn read_in_small_pieces() {
+        let mut reader = NormalizingReader::new(&b"one \r\ntwo\r\n"[..], TextNormalization::all());
+        let mut normalized = vec![];
+        let mut buf = [0; 3];
+
+        loop {
+            match reader.read(&mut buf).unwrap() {
+                0 => break,
+                read => normalized.extend_from_slice(&buf[..read]),
+            }
+        }
+
+        assert_eq!(normalized, b"one\ntwo\n");
+    }
+}
@@ -0,0 +1,135 @@
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::check::format_checksum_line;
+use crate::HashToolError;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// `sha256sum` compatible manifest lines.
+    #[default]
+    Text,
+    /// Array of `HashRecord` objects.
+    Json,
+    /// Digests only, one per line.
+    Bare,
+}
+
+impl FromStr for OutputFormat {
+    type Err = HashToolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "bare" => Ok(OutputFormat::Bare),
+            _ => Err(HashToolError::Usage(format!("unknown output format '{s}'"))),
+        }
+    }
+}
+
+/// Outcome of hashing a single file. Exactly one of `digest` and `error` is set.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HashRecord {
+    pub path: String,
+    pub algorithm: String,
+    pub digest: Option<String>,
+    pub size: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Writes the records in the given format. Failed records only show up in JSON, for the other
+/// formats the caller reports them on stderr.
+pub fn write_records(
+    out: &mut impl Write,
+    records: &[HashRecord],
+    format: OutputFormat,
+) -> io::Result<()> {
+    if format == OutputFormat::Json {
+        serde_json::to_writer_pretty(&mut *out, records)?;
+        return writeln!(out);
+    }
+
+    for record in records {
+        let digest = match &record.digest {
+            Some(digest) => digest,
+            None => continue,
+        };
+
+        match format {
+            OutputFormat::Bare => writeln!(out, "{digest}")?,
+            _ => writeln!(out, "{}", format_checksum_line(digest, &record.path))?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::output::{write_records, HashRecord, OutputFormat};
+
+    fn records() -> Vec<HashRecord> {
+        vec![
+            HashRecord {
+                path: String::from("test-file-1.txt"),
+                algorithm: String::from("sha256"),
+                digest: Some(String::from("cd6c8edf")),
+                size: Some(9),
+                error: None,
+            },
+            HashRecord {
+                path: String::from("non-existent"),
+                algorithm: String::from("sha256"),
+                digest: None,
+                size: None,
+                error: Some(String::from("non-existent: no such file or directory")),
+            },
+        ]
+    }
+
+    fn render(format: OutputFormat) -> String {
+        let mut out = vec![];
+        write_records(&mut out, &records(), format).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!("bare".parse::<OutputFormat>().unwrap(), OutputFormat::Bare);
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn write_text_and_bare() {
+        assert_eq!(render(OutputFormat::Text), "cd6c8edf  test-file-1.txt\n");
+        assert_eq!(render(OutputFormat::Bare), "cd6c8edf\n");
+    }
+
+    #[test]
+    fn write_json() {
+        let json: serde_json::Value = serde_json::from_str(&render(OutputFormat::Json)).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {
+                    "path": "test-file-1.txt",
+                    "algorithm": "sha256",
+                    "digest": "cd6c8edf",
+                    "size": 9,
+                    "error": null
+                },
+                {
+                    "path": "non-existent",
+                    "algorithm": "sha256",
+                    "digest": null,
+                    "size": null,
+                    "error": "non-existent: no such file or directory"
+                }
+            ])
+        );
+    }
+}
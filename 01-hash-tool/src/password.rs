@@ -0,0 +1,288 @@
+use std::str::FromStr;
+
+use argon2::Argon2;
+use password_hash::rand_core::{OsRng, RngCore};
+use password_hash::{PasswordHash, PasswordHasher, SaltString};
+use pbkdf2::Pbkdf2;
+use scrypt::Scrypt;
+
+use crate::HashToolError;
+
+/// Length of the generated salts, the 16 bytes bcrypt requires are plenty for the others too.
+pub const SALT_LEN: usize = 16;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum PasswordAlgorithm {
+    #[default]
+    Argon2id,
+    Bcrypt,
+    Scrypt,
+    /// PBKDF2 with HMAC-SHA256.
+    Pbkdf2,
+}
+
+impl FromStr for PasswordAlgorithm {
+    type Err = HashToolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "argon2id" | "argon2" => Ok(PasswordAlgorithm::Argon2id),
+            "bcrypt" => Ok(PasswordAlgorithm::Bcrypt),
+            "scrypt" => Ok(PasswordAlgorithm::Scrypt),
+            "pbkdf2" | "pbkdf2-sha256" => Ok(PasswordAlgorithm::Pbkdf2),
+            _ => Err(HashToolError::UnknownAlgorithm(s.to_string())),
+        }
+    }
+}
+
+/// Cost parameters, defaults follow the OWASP password storage recommendations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PasswordParams {
+    Argon2id {
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+    Bcrypt {
+        cost: u32,
+    },
+    Scrypt {
+        log_n: u8,
+        r: u32,
+        p: u32,
+    },
+    Pbkdf2 {
+        rounds: u32,
+    },
+}
+
+impl PasswordParams {
+    pub fn recommended(algorithm: PasswordAlgorithm) -> Self {
+        match algorithm {
+            PasswordAlgorithm::Argon2id => PasswordParams::Argon2id {
+                memory_kib: argon2::Params::DEFAULT_M_COST,
+                iterations: argon2::Params::DEFAULT_T_COST,
+                parallelism: argon2::Params::DEFAULT_P_COST,
+            },
+            PasswordAlgorithm::Bcrypt => PasswordParams::Bcrypt {
+                cost: bcrypt::DEFAULT_COST,
+            },
+            PasswordAlgorithm::Scrypt => PasswordParams::Scrypt {
+                log_n: scrypt::Params::RECOMMENDED_LOG_N,
+                r: scrypt::Params::RECOMMENDED_R,
+                p: scrypt::Params::RECOMMENDED_P,
+            },
+            PasswordAlgorithm::Pbkdf2 => PasswordParams::Pbkdf2 {
+                rounds: pbkdf2::Params::RECOMMENDED_ROUNDS as u32,
+            },
+        }
+    }
+
+    /// Overrides the recommended parameters with `name=value` pairs separated by commas. Names are
+    /// the ones used in PHC strings: `m`, `t`, `p` for Argon2id, `ln`, `r`, `p` for scrypt, `i` for
+    /// PBKDF2 and `cost` for bcrypt.
+    pub fn parse(algorithm: PasswordAlgorithm, text: &str) -> Result<Self, HashToolError> {
+        let mut params = PasswordParams::recommended(algorithm);
+
+        for pair in text.split(',').filter(|pair| !pair.is_empty()) {
+            let invalid = || HashToolError::Usage(format!("invalid cost parameter '{pair}'"));
+            let (name, value) = pair.split_once('=').ok_or_else(invalid)?;
+            let value: u32 = value.parse().map_err(|_e| invalid())?;
+
+            match (&mut params, name) {
+                (PasswordParams::Argon2id { memory_kib, .. }, "m") => *memory_kib = value,
+                (PasswordParams::Argon2id { iterations, .. }, "t") => *iterations = value,
+                (PasswordParams::Argon2id { parallelism, .. }, "p") => *parallelism = value,
+                (PasswordParams::Bcrypt { cost }, "cost") => *cost = value,
+                (PasswordParams::Scrypt { log_n, .. }, "ln") => {
+                    *log_n = value.try_into().map_err(|_e| invalid())?
+                }
+                (PasswordParams::Scrypt { r, .. }, "r") => *r = value,
+                (PasswordParams::Scrypt { p, .. }, "p") => *p = value,
+                (PasswordParams::Pbkdf2 { rounds }, "i") => *rounds = value,
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(params)
+    }
+}
+
+/// `hash-tool password hash|verify`, the password itself is read from the standard input so it
+/// doesn't end up in the shell history.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PasswordCommand {
+    Hash(PasswordParams),
+    /// Verify against the given hash string.
+    Verify(String),
+}
+
+/// Hashes `password` with a random salt. The result is a PHC string, except for bcrypt which only
+/// has its own `$2b$` format.
+pub fn hash_password(password: &[u8], params: &PasswordParams) -> Result<String, HashToolError> {
+    let mut salt = [0; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+
+    hash_password_with_salt(password, params, &salt)
+}
+
+pub fn hash_password_with_salt(
+    password: &[u8],
+    params: &PasswordParams,
+    salt: &[u8],
+) -> Result<String, HashToolError> {
+    let invalid =
+        |e: &dyn std::fmt::Display| HashToolError::Usage(format!("cannot hash password: {e}"));
+
+    if let PasswordParams::Bcrypt { cost } = *params {
+        let salt: [u8; SALT_LEN] = salt
+            .try_into()
+            .map_err(|_e| HashToolError::Usage(format!("bcrypt salt must be {SALT_LEN} bytes")))?;
+        return bcrypt::hash_with_salt(password, cost, salt)
+            .map(|parts| parts.format_for_version(bcrypt::Version::TwoB))
+            .map_err(|e| invalid(&e));
+    }
+
+    let salt = SaltString::encode_b64(salt).map_err(|e| invalid(&e))?;
+    let hash = match *params {
+        PasswordParams::Argon2id {
+            memory_kib,
+            iterations,
+            parallelism,
+        } => {
+            let params = argon2::Params::new(memory_kib, iterations, parallelism, None)
+                .map_err(|e| invalid(&e))?;
+            Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+                .hash_password(password, &salt)
+        }
+        PasswordParams::Scrypt { log_n, r, p } => {
+            let params = scrypt::Params::new(log_n, r, p, scrypt::Params::RECOMMENDED_LEN)
+                .map_err(|e| invalid(&e))?;
+            Scrypt.hash_password_customized(password, None, None, params, &salt)
+        }
+        PasswordParams::Pbkdf2 { rounds } => {
+            let params = pbkdf2::Params {
+                rounds,
+                ..pbkdf2::Params::default()
+            };
+            Pbkdf2.hash_password_customized(password, None, None, params, &salt)
+        }
+        PasswordParams::Bcrypt { .. } => unreachable!("bcrypt is handled above"),
+    };
+
+    hash.map(|hash| hash.to_string()).map_err(|e| invalid(&e))
+}
+
+/// Checks `password` against a hash made by `hash_password` or any other tool using the same
+/// formats. The algorithm and cost are taken from the hash itself.
+pub fn verify_password(password: &[u8], hash: &str) -> Result<bool, HashToolError> {
+    let invalid = || HashToolError::InvalidPasswordHash(hash.to_string());
+
+    if hash.starts_with("$2") {
+        return bcrypt::verify(password, hash).map_err(|_e| invalid());
+    }
+
+    let parsed = PasswordHash::new(hash).map_err(|_e| invalid())?;
+    match parsed.verify_password(&[&Argon2::default(), &Scrypt, &Pbkdf2], password) {
+        Ok(()) => Ok(true),
+        Err(password_hash::Error::Password) => Ok(false),
+        Err(_e) => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::password::{
+        hash_password, hash_password_with_salt, verify_password, PasswordAlgorithm, PasswordParams,
+    };
+
+    #[test]
+    fn argon2id_reference_vector() {
+        // from the test suite of the Argon2 reference implementation
+        let hash =
+            "$argon2id$v=19$m=256,t=2,p=2$c29tZXNhbHQ$bQk8UB/VmZZF4Oo79iDXuL5/0ttZwg2f/5U52iv1cDc";
+        let params = PasswordParams::parse(PasswordAlgorithm::Argon2id, "m=256,t=2,p=2").unwrap();
+
+        assert_eq!(
+            hash_password_with_salt(b"password", &params, b"somesalt").unwrap(),
+            hash
+        );
+        assert!(verify_password(b"password", hash).unwrap());
+        assert!(!verify_password(b"Password", hash).unwrap());
+    }
+
+    #[test]
+    fn bcrypt_openbsd_vectors() {
+        // from the jBCrypt test suite, which checks against OpenBSD
+        let vectors = [
+            (
+                "",
+                "$2a$06$DCq7YPn5Rq63x1Lad4cll.TV4S6ytwfsfvkgY8jIucDrjc8deX1s.",
+            ),
+            (
+                "a",
+                "$2a$06$m0CrhHm10qJ3lXRY.5zDGO3rS2KdeeWLuGmsfGlMfOxih58VYVfxe",
+            ),
+        ];
+
+        for (password, hash) in vectors {
+            assert!(
+                verify_password(password.as_bytes(), hash).unwrap(),
+                "{hash}"
+            );
+            assert!(!verify_password(b"wrong", hash).unwrap(), "{hash}");
+        }
+    }
+
+    #[test]
+    fn rfc7914_vectors() {
+        // section 11 (PBKDF2-HMAC-SHA256) and section 12 (scrypt) as PHC strings
+        let pbkdf2 = "$pbkdf2-sha256$i=1,l=64$c2FsdA$VawEblbjCJ/sFpHCJUS2BflBhSFt3gRl5oudV8INrLxJypzM8Xm2RZkWZLOdd+8xfHG4RbHjC9UJESBB06GXgw";
+        let scrypt = "$scrypt$ln=10,r=8,p=16$TmFDbA$/bq+HJ00cgB4VucZDQHp/nxq18vII3gw53N2Y0s3MWIurzDZLiKjiG/xCSedmDDaxyevuUqD7m2DYMvfoswGQA";
+
+        assert!(verify_password(b"passwd", pbkdf2).unwrap());
+        assert!(!verify_password(b"password", pbkdf2).unwrap());
+        assert!(verify_password(b"password", scrypt).unwrap());
+        assert!(!verify_password(b"passwd", scrypt).unwrap());
+    }
+
+    #[test]
+    fn hash_and_verify_each_algorithm() {
+        let cheap = [
+            (
+                PasswordAlgorithm::Argon2id,
+                "m=64,t=1",
+                "$argon2id$v=19$m=64,t=1,p=1$",
+            ),
+            (PasswordAlgorithm::Bcrypt, "cost=4", "$2b$04$"),
+            (PasswordAlgorithm::Scrypt, "ln=4", "$scrypt$ln=4,r=8,p=1$"),
+            (
+                PasswordAlgorithm::Pbkdf2,
+                "i=1000",
+                "$pbkdf2-sha256$i=1000,l=32$",
+            ),
+        ];
+
+        for (algorithm, cost, prefix) in cheap {
+            let params = PasswordParams::parse(algorithm, cost).unwrap();
+            let hash = hash_password(b"hunter2", &params).unwrap();
+
+            assert!(hash.starts_with(prefix), "{hash}");
+            assert!(verify_password(b"hunter2", &hash).unwrap(), "{hash}");
+            assert!(!verify_password(b"hunter3", &hash).unwrap(), "{hash}");
+        }
+    }
+
+    #[test]
+    fn invalid_params_and_hashes() {
+        assert!(PasswordParams::parse(PasswordAlgorithm::Bcrypt, "m=64").is_err());
+        assert!(PasswordParams::parse(PasswordAlgorithm::Scrypt, "ln=300").is_err());
+        assert!("md5-crypt".parse::<PasswordAlgorithm>().is_err());
+        assert_eq!(
+            verify_password(b"password", "5f4dcc3b5aa765d61d8327deb882cf99")
+                .unwrap_err()
+                .to_string(),
+            "'5f4dcc3b5aa765d61d8327deb882cf99' is not a supported password hash"
+        );
+    }
+}
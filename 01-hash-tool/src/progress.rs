@@ -0,0 +1,185 @@
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+const BAR_WIDTH: usize = 30;
+
+/// Bytes hashed so far, shared between the hashing threads and the progress bar.
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    bytes: Arc<AtomicU64>,
+}
+
+impl Progress {
+    pub fn add(&self, bytes: u64) {
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+}
+
+/// Counts everything read through it.
+#[derive(Debug)]
+pub struct ProgressReader<R> {
+    reader: R,
+    progress: Progress,
+}
+
+impl<R: Read> ProgressReader<R> {
+    pub fn new(reader: R, progress: Progress) -> Self {
+        ProgressReader { reader, progress }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.progress.add(read as u64);
+        Ok(read)
+    }
+}
+
+/// Redraws a single status line on stderr from a background thread until finished. Nothing is
+/// drawn for inputs done within the first redraw interval.
+pub struct ProgressBar {
+    stop: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl ProgressBar {
+    /// `total` is unknown when reading from the standard input, the bar then has no ETA.
+    pub fn start(progress: Progress, total: Option<u64>) -> ProgressBar {
+        let (stop, stopped) = mpsc::channel();
+        let started = Instant::now();
+
+        let thread = thread::spawn(move || {
+            let mut drawn = false;
+
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(REDRAW_INTERVAL) {
+                let line = render(progress.bytes(), total, started.elapsed());
+                eprint!("\r{line}\x1b[K");
+                let _ = io::stderr().flush();
+                drawn = true;
+            }
+
+            if drawn {
+                // clear the line, so the bar doesn't mix with later messages
+                eprint!("\r\x1b[K");
+            }
+        });
+
+        ProgressBar { stop, thread }
+    }
+
+    pub fn finish(self) {
+        let _ = self.stop.send(());
+        let _ = self.thread.join();
+    }
+}
+
+/// e.g. `[#########                     ] 3.1 GB / 10.0 GB  512.3 MB/s  ETA 0:13`
+pub fn render(bytes: u64, total: Option<u64>, elapsed: Duration) -> String {
+    let rate = throughput(bytes, elapsed);
+
+    match total {
+        Some(total) if total > 0 => {
+            let done = bytes.min(total);
+            let filled = (done as u128 * BAR_WIDTH as u128 / total as u128) as usize;
+            let eta = if rate > 0.0 {
+                format_duration(Duration::from_secs_f64((total - done) as f64 / rate))
+            } else {
+                String::from("-:--")
+            };
+
+            format!(
+                "[{}{}] {} / {}  {}/s  ETA {eta}",
+                "#".repeat(filled),
+                " ".repeat(BAR_WIDTH - filled),
+                format_bytes(done),
+                format_bytes(total),
+                format_bytes(rate as u64),
+            )
+        }
+        _ => format!("{}  {}/s", format_bytes(bytes), format_bytes(rate as u64)),
+    }
+}
+
+/// Bytes per second.
+pub fn throughput(bytes: u64, elapsed: Duration) -> f64 {
+    match elapsed.as_secs_f64() {
+        secs if secs > 0.0 => bytes as f64 / secs,
+        _ => 0.0,
+    }
+}
+
+/// Decimal units, so MB/s means the same as in disk and network specs.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["kB", "MB", "GB", "TB", "PB"];
+
+    if bytes < 1000 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    match secs {
+        0..=3599 => format!("{}:{:02}", secs / 60, secs % 60),
+        _ => format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+    use std::time::Duration;
+
+    use crate::progress::{format_bytes, render, Progress, ProgressReader};
+
+    #[test]
+    fn count_bytes_read() {
+        let progress = Progress::default();
+        let mut reader = ProgressReader::new(Cursor::new(vec![0; 1000]), progress.clone());
+
+        std::io::copy(&mut reader, &mut std::io::sink()).unwrap();
+        assert_eq!(reader.read(&mut [0; 8]).unwrap(), 0);
+
+        assert_eq!(progress.bytes(), 1000);
+    }
+
+    #[test]
+    fn render_progress_line() {
+        let elapsed = Duration::from_secs(2);
+
+        assert_eq!(
+            render(50_000_000, Some(200_000_000), elapsed),
+            format!(
+                "[{}{}] 50.0 MB / 200.0 MB  25.0 MB/s  ETA 0:06",
+                "#".repeat(7),
+                " ".repeat(23)
+            )
+        );
+        assert_eq!(render(1500, None, elapsed), "1.5 kB  750 B/s");
+    }
+
+    #[test]
+    fn human_readable_sizes() {
+        assert_eq!(format_bytes(999), "999 B");
+        assert_eq!(format_bytes(1_000), "1.0 kB");
+        assert_eq!(format_bytes(3_100_000_000), "3.1 GB");
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+
+/// Fixed size pool of worker threads, same design as the one in `web-server`, without the
+/// per job logging.
+pub struct ThreadPool {
+    workers: Vec<Worker>,
+    sender: Option<mpsc::Sender<Job>>,
+}
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+impl ThreadPool {
+    /// Create a new ThreadPool
+    ///
+    /// The size is the number of threads in the pool.
+    ///
+    /// # Panics
+    ///
+    /// The `new` function will panic if the size is zero.
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0);
+
+        let (sender, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let mut workers = Vec::with_capacity(size);
+
+        for _ in 0..size {
+            workers.push(Worker::new(Arc::clone(&receiver)));
+        }
+
+        ThreadPool {
+            workers,
+            sender: Some(sender),
+        }
+    }
+
+    pub fn execute<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let job = Box::new(f);
+        self.sender
+            .as_ref()
+            .expect("CHANNEL_CLOSED")
+            .send(job)
+            .unwrap();
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        // closing the channel makes every worker leave its loop once the queue is drained
+        drop(self.sender.take());
+
+        for worker in &mut self.workers {
+            if let Some(thread) = worker.thread.take() {
+                thread.join().unwrap();
+            }
+        }
+    }
+}
+
+struct Worker {
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Worker {
+    fn new(receiver: Arc<Mutex<mpsc::Receiver<Job>>>) -> Worker {
+        let thread = thread::spawn(move || loop {
+            // the lock is released at the end of the `let` statement, before the job runs
+            let message = receiver.lock().unwrap().recv();
+
+            match message {
+                Ok(job) => job(),
+                Err(_) => break,
+            }
+        });
+
+        Worker {
+            thread: Some(thread),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn test_pool_creation() {
+        let pool = ThreadPool::new(5);
+        assert_eq!(pool.workers.len(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed")]
+    fn test_pool_creation_with_zero_size() {
+        ThreadPool::new(0);
+    }
+
+    #[test]
+    fn test_drop_waits_for_jobs() {
+        let result = Arc::new(AtomicUsize::new(0));
+        {
+            let pool = ThreadPool::new(4);
+
+            for _ in 0..10 {
+                let result_clone = Arc::clone(&result);
+                pool.execute(move || {
+                    result_clone.fetch_add(10, Ordering::SeqCst);
+                });
+            }
+        }
+
+        assert_eq!(result.load(Ordering::SeqCst), 100);
+    }
+}
@@ -0,0 +1,195 @@
+use std::path::Path;
+
+use glob::Pattern;
+use walkdir::WalkDir;
+
+use crate::{HashToolError, STDIN_PATH};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct WalkOptions {
+    /// Descend into directories instead of reporting them as unreadable files.
+    pub recursive: bool,
+    /// Follow symlinks found while walking a directory, otherwise they are skipped.
+    /// Paths given explicitly are always followed, like `sha256sum` does.
+    pub follow_symlinks: bool,
+    /// Glob patterns matched against both the whole path and the file name.
+    pub exclude: Vec<String>,
+}
+
+/// Expands `paths` into the list of files to hash.
+///
+/// Order is deterministic: arguments keep their order and directory entries are sorted by name.
+/// Entries which can't be walked are still returned, so that the failure is reported when the
+/// file is hashed.
+pub fn collect_files(
+    paths: &[String],
+    options: &WalkOptions,
+) -> Result<Vec<String>, HashToolError> {
+    let mut exclude = Vec::with_capacity(options.exclude.len());
+    for pattern in &options.exclude {
+        match Pattern::new(pattern) {
+            Ok(pattern) => exclude.push(pattern),
+            Err(_e) => return Err(HashToolError::InvalidPattern(pattern.clone())),
+        }
+    }
+
+    let mut files = vec![];
+
+    for path in paths {
+        if path == STDIN_PATH {
+            files.push(path.clone());
+            continue;
+        }
+
+        if is_excluded(Path::new(path), &exclude) {
+            continue;
+        }
+
+        if !options.recursive || !Path::new(path).is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+
+        let walker = WalkDir::new(path)
+            .follow_links(options.follow_symlinks)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !is_excluded(entry.path(), &exclude));
+
+        for entry in walker {
+            match entry {
+                Ok(entry) if entry.file_type().is_file() => {
+                    files.push(entry.path().to_string_lossy().into_owned())
+                }
+                // directories are walked and symlinks are only seen when they are not followed
+                Ok(_entry) => {}
+                Err(e) => {
+                    if let Some(path) = e.path() {
+                        files.push(path.to_string_lossy().into_owned());
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn is_excluded(path: &Path, exclude: &[Pattern]) -> bool {
+    let file_name = path.file_name().map(|name| name.to_string_lossy());
+
+    exclude.iter().any(|pattern| {
+        pattern.matches_path(path) || file_name.as_ref().is_some_and(|name| pattern.matches(name))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::test_dir::TestDir;
+    use crate::walk::{collect_files, WalkOptions};
+
+    /// Creates a small directory tree.
+    fn prepare_tree(name: &str) -> TestDir {
+        let root = TestDir::new(name);
+        root.write("z.txt", "z");
+        root.write("a/1.txt", "1");
+        root.write("b/2.log", "2");
+        root.write("b/nested/3.txt", "3");
+
+        root
+    }
+
+    fn relative(files: Vec<String>, root: &Path) -> Vec<String> {
+        files
+            .into_iter()
+            .map(|file| {
+                PathBuf::from(file)
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn collect_explicit_paths() {
+        let paths = vec![String::from("test.txt"), String::from("-")];
+
+        let files = collect_files(&paths, &WalkOptions::default()).unwrap();
+
+        assert_eq!(files, paths);
+    }
+
+    #[test]
+    fn collect_recursive_sorted() {
+        let root = prepare_tree("sorted");
+        let options = WalkOptions {
+            recursive: true,
+            ..WalkOptions::default()
+        };
+
+        let files = collect_files(&[root.path_string()], &options).unwrap();
+
+        assert_eq!(
+            relative(files, root.path()),
+            vec!["a/1.txt", "b/2.log", "b/nested/3.txt", "z.txt"]
+        );
+    }
+
+    #[test]
+    fn collect_recursive_with_exclude() {
+        let root = prepare_tree("exclude");
+        let options = WalkOptions {
+            recursive: true,
+            exclude: vec![String::from("*.log"), String::from("nested")],
+            ..WalkOptions::default()
+        };
+
+        let files = collect_files(&[root.path_string()], &options).unwrap();
+
+        assert_eq!(relative(files, root.path()), vec!["a/1.txt", "z.txt"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn collect_recursive_symlinks() {
+        let root = prepare_tree("symlinks");
+        std::os::unix::fs::symlink(root.join("a"), root.join("link")).unwrap();
+        let mut options = WalkOptions {
+            recursive: true,
+            ..WalkOptions::default()
+        };
+        let paths = [root.path_string()];
+
+        let skipped = collect_files(&paths, &options).unwrap();
+        options.follow_symlinks = true;
+        let followed = collect_files(&paths, &options).unwrap();
+
+        assert_eq!(skipped.len(), 4);
+        assert_eq!(
+            relative(followed, root.path()),
+            vec![
+                "a/1.txt",
+                "b/2.log",
+                "b/nested/3.txt",
+                "link/1.txt",
+                "z.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn collect_invalid_pattern() {
+        let options = WalkOptions {
+            exclude: vec![String::from("[")],
+            ..WalkOptions::default()
+        };
+
+        let error = collect_files(&[String::from("test.txt")], &options).unwrap_err();
+
+        assert_eq!(error.to_string(), "invalid exclude pattern '['");
+    }
+}
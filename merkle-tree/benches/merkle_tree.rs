@@ -0,0 +1,71 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use merkle_tree::{FlatMerkleTree, MerkleTree};
+
+const SIZES: [usize; 3] = [16, 1_000, 100_000];
+
+fn leaves(len: usize) -> Vec<[u8; 8]> {
+    (0..len as u64).map(u64::to_be_bytes).collect()
+}
+
+fn build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+
+    for len in SIZES {
+        let leaves = leaves(len);
+
+        group.bench_with_input(BenchmarkId::new("rc", len), &leaves, |b, leaves| {
+            b.iter(|| MerkleTree::from_leaves(black_box(leaves)))
+        });
+        group.bench_with_input(BenchmarkId::new("flat", len), &leaves, |b, leaves| {
+            b.iter(|| FlatMerkleTree::from_leaves(black_box(leaves)))
+        });
+    }
+
+    group.finish();
+}
+
+fn proof(c: &mut Criterion) {
+    let mut group = c.benchmark_group("proof");
+
+    for len in SIZES {
+        let leaves = leaves(len);
+        let tree = MerkleTree::from_leaves(&leaves);
+        let flat = FlatMerkleTree::from_leaves(&leaves);
+
+        group.bench_with_input(BenchmarkId::new("rc", len), &len, |b, len| {
+            b.iter(|| tree.proof(black_box(len / 2)))
+        });
+        group.bench_with_input(BenchmarkId::new("flat", len), &len, |b, len| {
+            b.iter(|| flat.proof(black_box(len / 2)))
+        });
+    }
+
+    group.finish();
+}
+
+fn append(c: &mut Criterion) {
+    let mut group = c.benchmark_group("append");
+
+    for len in SIZES {
+        let leaves = leaves(len + 1);
+        let tree = MerkleTree::from_leaves(&leaves[..len]);
+
+        group.bench_with_input(BenchmarkId::new("push", len), &leaves[len], |b, leaf| {
+            b.iter_batched(
+                || tree.clone(),
+                |mut tree| tree.push(black_box(leaf)),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("rebuild", len), &leaves, |b, leaves| {
+            b.iter(|| MerkleTree::from_leaves(black_box(leaves)))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, build, proof, append);
+criterion_main!(benches);
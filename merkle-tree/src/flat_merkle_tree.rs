@@ -0,0 +1,194 @@
+use crate::merkle_tree::{Hash, Position, Proof, Sibling};
+use crate::utils::{self, HashScheme};
+
+/// Same tree as `MerkleTree`, stored in one `Vec` the way a binary heap is: the root is at index
+/// 1, the children of node `i` are at `2 * i` and `2 * i + 1`, and the leaves take the last half.
+/// Index 0 is unused, as are the slots right of the nodes of a tree which isn't full. A node moved
+/// up a level unchanged is copied into its parent's slot.
+#[derive(Clone, Debug)]
+pub struct FlatMerkleTree {
+    nodes: Vec<Hash>,
+    scheme: HashScheme,
+    /// Number of leaves, without the repeated ones.
+    len: usize,
+    /// Number of levels below the root.
+    depth: u32,
+}
+
+const ROOT: usize = 1;
+
+fn parent(index: usize) -> usize {
+    index / 2
+}
+
+fn sibling(index: usize) -> usize {
+    index ^ 1
+}
+
+fn children(index: usize) -> (usize, usize) {
+    (2 * index, 2 * index + 1)
+}
+
+impl FlatMerkleTree {
+    /// Builds the tree with the default, RFC 6962, hashing scheme.
+    pub fn from_leaves(leaves: &[impl AsRef<[u8]>]) -> FlatMerkleTree {
+        FlatMerkleTree::from_leaves_with(leaves, HashScheme::default())
+    }
+
+    /// Builds the tree level by level from the leaves up. The root of an empty tree is the hash
+    /// of no data.
+    pub fn from_leaves_with(leaves: &[impl AsRef<[u8]>], scheme: HashScheme) -> FlatMerkleTree {
+        let depth = match leaves.len() {
+            0 => 0,
+            // a single leaf is paired with itself
+            len if scheme.pairs_odd_node() => len.next_power_of_two().trailing_zeros().max(1),
+            len => len.next_power_of_two().trailing_zeros(),
+        };
+        let first_leaf = 1 << depth;
+
+        let mut nodes = vec![[0; 32]; 2 * first_leaf];
+        if leaves.is_empty() {
+            nodes[ROOT] = utils::hash(&[]);
+        }
+        for (i, leaf) in leaves.iter().enumerate() {
+            nodes[first_leaf + i] = scheme.hash_leaf(leaf.as_ref());
+        }
+
+        let mut level_start = first_leaf;
+        let mut level_len = leaves.len();
+        while level_start > ROOT {
+            // if needed, make the level even by repeating its last node
+            if scheme.pairs_odd_node() && !level_len.is_multiple_of(2) {
+                nodes[level_start + level_len] = nodes[level_start + level_len - 1];
+                level_len += 1;
+            }
+
+            let level_end = level_start + level_len;
+            for node in parent(level_start)..parent(level_end + 1) {
+                let (left, right) = children(node);
+                nodes[node] = if right < level_end {
+                    scheme.hash_node(&nodes[left], &nodes[right])
+                } else {
+                    // the last node of an odd level moves up as it is
+                    nodes[left]
+                };
+            }
+
+            level_start /= 2;
+            level_len = level_len.div_ceil(2);
+        }
+
+        FlatMerkleTree {
+            nodes,
+            scheme,
+            len: leaves.len(),
+            depth,
+        }
+    }
+
+    pub fn root(&self) -> &Hash {
+        &self.nodes[ROOT]
+    }
+
+    pub fn scheme(&self) -> HashScheme {
+        self.scheme
+    }
+
+    /// Number of leaves the tree was built from.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Proof that the leaf at `index` is part of the tree, `None` if there is no such leaf.
+    pub fn proof(&self, index: usize) -> Option<Proof> {
+        if index >= self.len {
+            return None;
+        }
+
+        let leaf = (1 << self.depth) + index;
+        let mut siblings = Vec::with_capacity(self.depth as usize);
+        let mut node = leaf;
+        let mut level_start = 1 << self.depth;
+        let mut level_len = self.len;
+        while node > ROOT {
+            if self.scheme.pairs_odd_node() && !level_len.is_multiple_of(2) {
+                level_len += 1;
+            }
+            // past the end of the level, the node was moved up without a sibling
+            if sibling(node) < level_start + level_len {
+                siblings.push(Sibling {
+                    hash: self.nodes[sibling(node)],
+                    position: match node % 2 {
+                        0 => Position::Right,
+                        _ => Position::Left,
+                    },
+                });
+            }
+            node = parent(node);
+            level_start /= 2;
+            level_len = level_len.div_ceil(2);
+        }
+
+        Some(Proof {
+            hash: self.nodes[leaf],
+            index,
+            len: self.len,
+            siblings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::flat_merkle_tree::FlatMerkleTree;
+    use crate::merkle_tree::MerkleTree;
+    use crate::utils::HashScheme;
+
+    const SCHEMES: [HashScheme; 2] = [HashScheme::Plain, HashScheme::Rfc6962];
+
+    #[test]
+    fn test_single_leaf() {
+        for scheme in SCHEMES {
+            let flat = FlatMerkleTree::from_leaves_with(&["hello 1"], scheme);
+            let tree = MerkleTree::from_leaves_with(&["hello 1"], scheme);
+
+            assert_eq!(flat.root(), tree.root());
+            assert_eq!(flat.proof(0), tree.proof(0));
+        }
+    }
+
+    #[test]
+    fn test_no_leaves() {
+        for scheme in SCHEMES {
+            let flat = FlatMerkleTree::from_leaves_with(&[] as &[&[u8]], scheme);
+            let tree = MerkleTree::from_leaves_with(&[] as &[&[u8]], scheme);
+
+            assert!(flat.is_empty());
+            assert_eq!(flat.root(), tree.root());
+            assert_eq!(flat.proof(0), None);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn same_roots_and_proofs_as_rc_tree(
+            leaves in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..16), 0..70),
+            scheme in prop::sample::select(SCHEMES.to_vec()),
+        ) {
+            let flat = FlatMerkleTree::from_leaves_with(&leaves, scheme);
+            let tree = MerkleTree::from_leaves_with(&leaves, scheme);
+
+            prop_assert_eq!(flat.root(), tree.root());
+            prop_assert_eq!(flat.len(), tree.len());
+            for index in 0..=leaves.len() {
+                prop_assert_eq!(flat.proof(index), tree.proof(index));
+            }
+        }
+    }
+}
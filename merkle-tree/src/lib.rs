@@ -1,2 +1,7 @@
+mod flat_merkle_tree;
 mod merkle_tree;
 mod utils;
+
+pub use flat_merkle_tree::*;
+pub use merkle_tree::*;
+pub use utils::HashScheme;
@@ -1,19 +1,28 @@
 use std::rc::Rc;
 
-use crate::utils;
+use crate::utils::{self, HashScheme};
 
-pub type Hash = String;
-// pub type Hash = [u8; 32];
+pub type Hash = [u8; 32];
 
-struct MerkleTree<'a> {
-    root: &'a MerkleTreeNode,
+/// Binary Merkle tree over SHA-256 hashes of the leaves. How the hashes are made, and what
+/// happens to the last node of a level with an odd number of nodes, depends on its `HashScheme`.
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    root: Rc<MerkleTreeNode>,
+    scheme: HashScheme,
+    /// Number of leaves, without the repeated ones.
+    len: usize,
+    /// Number of levels below the root.
+    depth: usize,
+    /// Roots of the full subtrees the leaves split into, one for each bit set in `len`, the
+    /// largest first. All `push` needs to get the new root.
+    frontier: Vec<Rc<MerkleTreeNode>>,
 }
 
 #[derive(Clone, PartialEq, Debug)]
 enum MerkleTreeNode {
     Leaf {
         hash: Hash,
-        original_data: Vec<u8>,
         index: usize,
     },
     Node {
@@ -26,38 +35,181 @@ enum MerkleTreeNode {
 impl MerkleTreeNode {
     fn get_hash(&self) -> &Hash {
         match self {
-            MerkleTreeNode::Leaf {
-                hash,
-                original_data,
-                index: _,
-            } => hash,
-            MerkleTreeNode::Node { hash, left, right } => hash,
+            MerkleTreeNode::Leaf { hash, .. } => hash,
+            MerkleTreeNode::Node { hash, .. } => hash,
         }
     }
+}
 
-    fn get_original_value(&self) -> &str {
-        match self {
-            MerkleTreeNode::Leaf {
-                hash,
-                original_data,
-                index: _,
-            } => match std::str::from_utf8(original_data) {
-                Ok(string) => string,
-                Err(e) => "",
-            },
-            MerkleTreeNode::Node { hash, left, right } => "",
+impl MerkleTree {
+    /// Builds the tree with the default, RFC 6962, hashing scheme.
+    pub fn from_leaves(leaves: &[impl AsRef<[u8]>]) -> MerkleTree {
+        MerkleTree::from_leaves_with(leaves, HashScheme::default())
+    }
+
+    /// Builds all levels of the tree. The root of an empty tree is the hash of no data.
+    pub fn from_leaves_with(leaves: &[impl AsRef<[u8]>], scheme: HashScheme) -> MerkleTree {
+        if leaves.is_empty() {
+            return MerkleTree {
+                root: Rc::new(MerkleTreeNode::Leaf {
+                    hash: utils::hash(&[]),
+                    index: 0,
+                }),
+                scheme,
+                len: 0,
+                depth: 0,
+                frontier: vec![],
+            };
+        }
+
+        let mut level = prepare_leaf_level(leaves.iter().map(AsRef::as_ref).collect(), scheme);
+        let mut depth = 0;
+        let mut frontier = vec![];
+        loop {
+            // the full subtrees come first on every level, the last one is on the frontier if
+            // there's no other one to pair it with
+            let full = leaves.len() >> depth;
+            if !full.is_multiple_of(2) {
+                frontier.push(level[full - 1].clone());
+            }
+            if level.len() == 1 {
+                break;
+            }
+            level = prepare_node_level(level, scheme);
+            depth += 1;
+        }
+        frontier.reverse();
+
+        MerkleTree {
+            root: level.remove(0),
+            scheme,
+            len: leaves.len(),
+            depth,
+            frontier,
+        }
+    }
+
+    /// Appends a leaf and updates the root in O(log n), the tree ends up the same as one built
+    /// with `from_leaves` from all the leaves.
+    pub fn push(&mut self, leaf: impl AsRef<[u8]>) {
+        let mut node = Rc::new(MerkleTreeNode::Leaf {
+            hash: self.scheme.hash_leaf(leaf.as_ref()),
+            index: self.len,
+        });
+
+        // like adding one to `len` in binary, two subtrees of the same size carry into one
+        let mut carry = self.len;
+        while !carry.is_multiple_of(2) {
+            let left = self.frontier.pop().expect("a subtree for every bit set");
+            node = join(left, node, self.scheme);
+            carry /= 2;
+        }
+        self.frontier.push(node);
+
+        self.len += 1;
+        self.depth = self.len.next_power_of_two().trailing_zeros() as usize;
+        if self.scheme.pairs_odd_node() {
+            // a single leaf is paired with itself
+            self.depth = self.depth.max(1);
+        }
+        self.root = self.root_from_frontier();
+    }
+
+    /// Joins the frontier into the root, from the smallest subtree up, the way `from_leaves`
+    /// joins the last nodes of the levels.
+    fn root_from_frontier(&self) -> Rc<MerkleTreeNode> {
+        let mut subtrees = self.frontier.iter().rev().cloned();
+        let mut right = None;
+
+        for height in 0..self.depth {
+            let left = match self.len >> height & 1 {
+                1 => subtrees.next(),
+                _ => None,
+            };
+            right = match (left, right) {
+                (Some(left), Some(right)) => Some(join(left, right, self.scheme)),
+                (Some(node), None) | (None, Some(node)) if self.scheme.pairs_odd_node() => {
+                    Some(join(node.clone(), node, self.scheme))
+                }
+                (node, None) | (None, node) => node,
+            };
+        }
+
+        // a tree of a power of two leaves is a single full subtree
+        right
+            .or_else(|| subtrees.next())
+            .expect("the tree isn't empty")
+    }
+
+    pub fn root(&self) -> &Hash {
+        self.root.get_hash()
+    }
+
+    pub fn scheme(&self) -> HashScheme {
+        self.scheme
+    }
+
+    /// Number of leaves the tree was built from.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Proof that the leaf at `index` is part of the tree, `None` if there is no such leaf.
+    pub fn proof(&self, index: usize) -> Option<Proof> {
+        if index >= self.len {
+            return None;
+        }
+
+        let mut node = &self.root;
+        let mut siblings = Vec::with_capacity(self.depth);
+
+        // every node covers a range of leaf positions, the index tells which half to go down to
+        let (mut start, mut end) = match self.scheme {
+            // repeated nodes fill the tree up to the next power of two, so it's always the middle
+            HashScheme::Plain => (0, 1 << self.depth),
+            // the left subtree takes the largest power of two below the number of leaves
+            HashScheme::Rfc6962 => (0, self.len),
+        };
+        while let MerkleTreeNode::Node { left, right, .. } = &**node {
+            let middle = match self.scheme {
+                HashScheme::Plain => start + (end - start) / 2,
+                HashScheme::Rfc6962 => start + (1 << (end - start - 1).ilog2()),
+            };
+            let (next, sibling, position) = if index < middle {
+                end = middle;
+                (left, right, Position::Right)
+            } else {
+                start = middle;
+                (right, left, Position::Left)
+            };
+            siblings.push(Sibling {
+                hash: *sibling.get_hash(),
+                position,
+            });
+            node = next;
         }
+        siblings.reverse();
+
+        Some(Proof {
+            hash: *node.get_hash(),
+            index,
+            len: self.len,
+            siblings,
+        })
     }
 }
 
-fn prepare_leaf_level(data_blocks: Vec<&[u8]>) -> Vec<Rc<MerkleTreeNode>> {
+fn prepare_leaf_level(data_blocks: Vec<&[u8]>, scheme: HashScheme) -> Vec<Rc<MerkleTreeNode>> {
     let mut leafs: Vec<_> = data_blocks
         .iter()
         .enumerate()
         .map(|(idx, data)| {
             Rc::new(MerkleTreeNode::Leaf {
-                original_data: data.to_vec(),
-                hash: utils::hash(*data),
+                hash: scheme.hash_leaf(data),
                 index: idx,
             })
         })
@@ -65,22 +217,22 @@ fn prepare_leaf_level(data_blocks: Vec<&[u8]>) -> Vec<Rc<MerkleTreeNode>> {
     let size = leafs.len();
 
     // if needed, make it even by cloning the last one
-    if size % 2 != 0 {
+    if scheme.pairs_odd_node() && !size.is_multiple_of(2) {
         leafs.push(leafs[size - 1].clone());
     }
 
     leafs
 }
 
-fn prepare_node_level(leaf_level: Vec<Rc<MerkleTreeNode>>) -> Vec<Rc<MerkleTreeNode>> {
+fn prepare_node_level(
+    leaf_level: Vec<Rc<MerkleTreeNode>>,
+    scheme: HashScheme,
+) -> Vec<Rc<MerkleTreeNode>> {
     let mut iter = leaf_level.into_iter();
     let mut result = vec![];
 
     loop {
-        let leaf_node_1 = iter.next();
-        let leaf_node_2 = iter.next();
-
-        let node_1 = match leaf_node_1 {
+        let node_1 = match iter.next() {
             Some(node) => node,
             None => {
                 // if None, it's the end of iterator, return result
@@ -88,79 +240,153 @@ fn prepare_node_level(leaf_level: Vec<Rc<MerkleTreeNode>>) -> Vec<Rc<MerkleTreeN
             }
         };
 
-        // if needed, make it even by cloning the last one
-        let node_2 = leaf_node_2.unwrap_or(node_1.clone());
-
-        let node_1_hash = match &*node_1 {
-            MerkleTreeNode::Node {
-                hash,
-                left: _,
-                right: _,
-            } => hash,
-            MerkleTreeNode::Leaf {
-                hash,
-                original_data: _,
-                index: _,
-            } => hash,
-        };
-
-        let node_2_hash = match &*node_2 {
-            MerkleTreeNode::Node {
-                hash,
-                left: _,
-                right: _,
-            } => hash,
-            MerkleTreeNode::Leaf {
-                hash,
-                original_data: _,
-                index: _,
-            } => hash,
+        let node_2 = match iter.next() {
+            Some(node) => node,
+            // if needed, make it even by cloning the last one
+            None if scheme.pairs_odd_node() => node_1.clone(),
+            // or move the last one up as it is
+            None => {
+                result.push(node_1);
+                return result;
+            }
         };
 
-        result.push(Rc::new(MerkleTreeNode::Node {
-            hash: utils::combine_and_hash(node_1_hash.as_bytes(), node_2_hash.as_bytes()),
-            left: Rc::clone(&node_1),
-            right: Rc::clone(&node_2),
-        }));
+        result.push(join(node_1, node_2, scheme));
     }
 }
 
-#[derive(Clone, Debug)]
-struct Proof {
-    hash: Hash,
-    index: usize,
-    siblings: Vec<Hash>,
+fn join(
+    left: Rc<MerkleTreeNode>,
+    right: Rc<MerkleTreeNode>,
+    scheme: HashScheme,
+) -> Rc<MerkleTreeNode> {
+    Rc::new(MerkleTreeNode::Node {
+        hash: scheme.hash_node(left.get_hash(), right.get_hash()),
+        left,
+        right,
+    })
 }
 
-fn build_proofs(node: Rc<MerkleTreeNode>, path: Vec<Hash>) -> Vec<Proof> {
-    match &*node {
-        MerkleTreeNode::Leaf {
-            hash,
-            original_data: _,
-            index,
-        } => vec![Proof {
-            hash: hash.clone(),
-            index: *index,
-            siblings: path,
-        }],
-        MerkleTreeNode::Node { hash, left, right } => vec![
-            build_proofs(
-                left.clone(),
-                vec![vec![right.get_hash().clone()], path.clone()].concat(),
-            ),
-            build_proofs(
-                right.clone(),
-                vec![vec![left.get_hash().clone()], path.clone()].concat(),
-            ),
-        ]
-        .concat(),
+/// Hash of a leaf and the hashes of its siblings on the way up to the root, the nearest first.
+/// A node moved up a level unchanged has no sibling there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Proof {
+    pub hash: Hash,
+    pub index: usize,
+    /// Number of leaves of the tree the proof was made for.
+    pub len: usize,
+    pub siblings: Vec<Sibling>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sibling {
+    pub hash: Hash,
+    /// Side the sibling is on, so the hashes are combined in the right order.
+    pub position: Position,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Position {
+    Left,
+    Right,
+}
+
+impl Proof {
+    /// Root of the tree the proof was made for, as long as it's a valid one.
+    pub fn derive_root(&self, scheme: HashScheme) -> Hash {
+        self.siblings
+            .iter()
+            .fold(self.hash, |hash, sibling| match sibling.position {
+                Position::Left => scheme.hash_node(&sibling.hash, &hash),
+                Position::Right => scheme.hash_node(&hash, &sibling.hash),
+            })
+    }
+
+    /// Whether `leaf_data` is the leaf at `index` in the tree of `tree_len` leaves with `root`.
+    /// The scheme, size and index come from the verifier, like the size in a signed tree head, not
+    /// from the proof, which could otherwise relabel itself; the sides of the siblings have to be
+    /// the ones they give.
+    pub fn verify(
+        &self,
+        root: &Hash,
+        tree_len: usize,
+        index: usize,
+        leaf_data: &[u8],
+        scheme: HashScheme,
+    ) -> bool {
+        index < tree_len
+            && self
+                .siblings
+                .iter()
+                .map(|sibling| sibling.position)
+                .eq(sibling_positions(index, tree_len, scheme))
+            && scheme.hash_leaf(leaf_data) == self.hash
+            && &self.derive_root(scheme) == root
     }
 }
 
+/// Sides of the siblings of the leaf at `index` in a tree of `len` leaves, the nearest first.
+fn sibling_positions(index: usize, len: usize, scheme: HashScheme) -> Vec<Position> {
+    let mut positions = vec![];
+    let (mut node, mut level_len) = (index, len);
+    // a single leaf is paired with itself too
+    if scheme.pairs_odd_node() && level_len == 1 {
+        level_len = 2;
+    }
+
+    while level_len > 1 {
+        if scheme.pairs_odd_node() && !level_len.is_multiple_of(2) {
+            level_len += 1;
+        }
+        // past the end of the level, the node is moved up without a sibling
+        if node ^ 1 < level_len {
+            positions.push(match node % 2 {
+                0 => Position::Right,
+                _ => Position::Left,
+            });
+        }
+        node /= 2;
+        level_len = level_len.div_ceil(2);
+    }
+
+    positions
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::merkle_tree::{build_proofs, prepare_leaf_level, prepare_node_level};
-    use crate::utils::combine_and_hash;
+    use proptest::prelude::*;
+
+    use crate::merkle_tree::{prepare_leaf_level, prepare_node_level, MerkleTree, Position, Proof};
+    use crate::utils::{hash, HashScheme};
+
+    /// Leaves of the Certificate Transparency tests, RFC 6962 roots of their first 1 to 8.
+    const CT_LEAVES: [&str; 8] = [
+        "",
+        "00",
+        "10",
+        "2021",
+        "3031",
+        "40414243",
+        "5051525354555657",
+        "606162636465666768696a6b6c6d6e6f",
+    ];
+    const CT_ROOTS: [&str; 8] = [
+        "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+        "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+        "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+        "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+        "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+        "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+        "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+        "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+    ];
+
+    fn ct_leaves(count: usize) -> Vec<Vec<u8>> {
+        CT_LEAVES[..count]
+            .iter()
+            .map(|leaf| hex::decode(leaf).unwrap())
+            .collect()
+    }
 
     #[test]
     fn test_prepare_leaf_level_even() {
@@ -171,7 +397,7 @@ mod tests {
             "hello 4".as_bytes(),
         ];
 
-        let leafs = prepare_leaf_level(data);
+        let leafs = prepare_leaf_level(data, HashScheme::Plain);
 
         assert_eq!(leafs.len(), 4);
     }
@@ -186,10 +412,12 @@ mod tests {
             "hello 5".as_bytes(),
         ];
 
-        let leafs = prepare_leaf_level(data);
-
+        let leafs = prepare_leaf_level(data.clone(), HashScheme::Plain);
         assert_eq!(leafs.len(), 6);
         assert_eq!(leafs[4], leafs[5]);
+
+        let leafs = prepare_leaf_level(data, HashScheme::Rfc6962);
+        assert_eq!(leafs.len(), 5);
     }
 
     #[test]
@@ -202,10 +430,10 @@ mod tests {
             "hello 4".as_bytes(),
             "hello 5".as_bytes(),
         ];
-        let leafs = prepare_leaf_level(data);
+        let leafs = prepare_leaf_level(data, HashScheme::Plain);
 
         // act
-        let nodes = prepare_node_level(leafs);
+        let nodes = prepare_node_level(leafs, HashScheme::Plain);
 
         // assert
         assert_eq!(nodes.len(), 3);
@@ -221,14 +449,212 @@ mod tests {
             "hello 4".as_bytes(),
             "hello 5".as_bytes(),
         ];
-        let leafs = prepare_leaf_level(data);
-        let nodes_1 = prepare_node_level(leafs);
+        let leafs = prepare_leaf_level(data.clone(), HashScheme::Plain);
+        let nodes_1 = prepare_node_level(leafs, HashScheme::Plain);
 
         // act
-        let nodes_2 = prepare_node_level(nodes_1);
+        let nodes_2 = prepare_node_level(nodes_1, HashScheme::Plain);
 
         // assert
         assert_eq!(nodes_2.len(), 2);
+
+        // the last leaf moves up as it is
+        let leafs = prepare_leaf_level(data, HashScheme::Rfc6962);
+        let nodes = prepare_node_level(leafs.clone(), HashScheme::Rfc6962);
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(nodes[2], leafs[4]);
+    }
+
+    #[test]
+    fn test_from_leaves() {
+        let scheme = HashScheme::Plain;
+        let tree = MerkleTree::from_leaves_with(&["hello 1", "hello 2", "hello 3"], scheme);
+
+        let hash_12 = scheme.hash_node(&hash(&[b"hello 1"]), &hash(&[b"hello 2"]));
+        let hash_33 = scheme.hash_node(&hash(&[b"hello 3"]), &hash(&[b"hello 3"]));
+        assert_eq!(tree.root(), &scheme.hash_node(&hash_12, &hash_33));
+        assert_eq!(tree.len(), 3);
+    }
+
+    #[test]
+    fn test_from_leaves_rfc6962() {
+        let tree = MerkleTree::from_leaves(&["hello 1", "hello 2", "hello 3"]);
+
+        let leaf = |data: &[u8]| hash(&[&[0x00], data]);
+        let node = |left: &[u8], right: &[u8]| hash(&[&[0x01], left, right]);
+        let hash_12 = node(&leaf(b"hello 1"), &leaf(b"hello 2"));
+        assert_eq!(tree.scheme(), HashScheme::Rfc6962);
+        assert_eq!(tree.root(), &node(&hash_12, &leaf(b"hello 3")));
+    }
+
+    #[test]
+    fn test_from_no_leaves() {
+        for scheme in [HashScheme::Plain, HashScheme::Rfc6962] {
+            let tree = MerkleTree::from_leaves_with(&[] as &[&[u8]], scheme);
+
+            assert!(tree.is_empty());
+            assert_eq!(
+                hex::encode(tree.root()),
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+            );
+            assert_eq!(tree.proof(0), None);
+        }
+    }
+
+    #[test]
+    fn test_certificate_transparency_roots() {
+        for (count, root) in (1..=8).zip(CT_ROOTS) {
+            let tree = MerkleTree::from_leaves(&ct_leaves(count));
+
+            assert_eq!(hex::encode(tree.root()), root, "{count} leaves");
+        }
+    }
+
+    #[test]
+    fn test_certificate_transparency_proofs() {
+        // (leaf, number of leaves, audit path)
+        let paths = [
+            (0, 1, vec![]),
+            (
+                0,
+                8,
+                vec![
+                    "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+                    "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                    "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4",
+                ],
+            ),
+            (
+                5,
+                8,
+                vec![
+                    "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+                    "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+                    "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+                ],
+            ),
+            (
+                2,
+                3,
+                vec!["fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125"],
+            ),
+            (
+                1,
+                5,
+                vec![
+                    "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+                    "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                    "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+                ],
+            ),
+        ];
+
+        for (index, count, path) in paths {
+            let leaves = ct_leaves(count);
+            let tree = MerkleTree::from_leaves(&leaves);
+            let proof = tree.proof(index).unwrap();
+
+            let siblings: Vec<_> = proof.siblings.iter().map(|s| hex::encode(s.hash)).collect();
+            assert_eq!(siblings, path, "leaf {index} of {count}");
+            assert!(proof.verify(
+                tree.root(),
+                count,
+                index,
+                &leaves[index],
+                HashScheme::Rfc6962
+            ));
+        }
+    }
+
+    #[test]
+    fn test_push() {
+        let mut tree = MerkleTree::from_leaves(&[] as &[&[u8]]);
+
+        for (leaf, root) in ct_leaves(8).into_iter().zip(CT_ROOTS) {
+            tree.push(&leaf);
+
+            assert_eq!(hex::encode(tree.root()), root, "{} leaves", tree.len());
+        }
+        let proof = tree.proof(5).unwrap();
+        assert!(proof.verify(tree.root(), 8, 5, &ct_leaves(6)[5], HashScheme::Rfc6962));
+    }
+
+    #[test]
+    fn test_leaf_is_not_a_node() {
+        // leaves made of the children of the nodes below the root
+        for (scheme, same_root) in [(HashScheme::Plain, true), (HashScheme::Rfc6962, false)] {
+            let tree = MerkleTree::from_leaves_with(&["a", "b", "c", "d"], scheme);
+            let leaf = |data: &[u8]| scheme.hash_leaf(data);
+            let forged = MerkleTree::from_leaves_with(
+                &[
+                    [leaf(b"a"), leaf(b"b")].concat(),
+                    [leaf(b"c"), leaf(b"d")].concat(),
+                ],
+                scheme,
+            );
+
+            assert_eq!(forged.root() == tree.root(), same_root);
+        }
+    }
+
+    #[test]
+    fn test_proof_cannot_pick_scheme() {
+        let tree = MerkleTree::from_leaves(&["a", "b"]);
+        let scheme = HashScheme::Rfc6962;
+        // a node's preimage passed off as a leaf, with a proof claiming the unprefixed scheme
+        let forged_leaf = [
+            &[0x01][..],
+            &scheme.hash_leaf(b"a"),
+            &scheme.hash_leaf(b"b"),
+        ]
+        .concat();
+        let forged = Proof {
+            hash: *tree.root(),
+            index: 0,
+            len: 1,
+            siblings: vec![],
+        };
+
+        assert_eq!(HashScheme::Plain.hash_leaf(&forged_leaf), forged.hash);
+        assert!(!forged.verify(tree.root(), 1, 0, &forged_leaf, scheme));
+    }
+
+    #[test]
+    fn test_proof_index_is_checked() {
+        let tree = MerkleTree::from_leaves(&["a", "b", "c"]);
+        let proof = tree.proof(0).unwrap();
+
+        assert!(!proof.verify(tree.root(), 3, 1, b"a", HashScheme::Rfc6962));
+        assert!(!proof.verify(tree.root(), 3, 3, b"a", HashScheme::Rfc6962));
+        let mut flipped = proof.clone();
+        flipped.siblings[0].position = Position::Left;
+        assert!(!flipped.verify(tree.root(), 3, 0, b"a", HashScheme::Rfc6962));
+        assert!(proof.verify(tree.root(), 3, 0, b"a", HashScheme::Rfc6962));
+    }
+
+    #[test]
+    fn test_relabelled_proof_is_rejected() {
+        let tree = MerkleTree::from_leaves(&["a", "b", "c"]);
+        let relabelled = Proof {
+            index: 1,
+            len: 2,
+            ..tree.proof(2).unwrap()
+        };
+
+        assert!(relabelled.verify(tree.root(), 3, 2, b"c", HashScheme::Rfc6962));
+        assert!(!relabelled.verify(tree.root(), 3, 1, b"c", HashScheme::Rfc6962));
+
+        // the duplicated last leaf of a Plain tree is not a 4th one
+        let tree = MerkleTree::from_leaves_with(&["a", "b", "c"], HashScheme::Plain);
+        let proof = tree.proof(2).unwrap();
+        let relabelled = Proof {
+            index: 3,
+            len: 4,
+            ..proof.clone()
+        };
+        assert_eq!(relabelled.derive_root(HashScheme::Plain), *tree.root());
+        assert!(!relabelled.verify(tree.root(), 3, 3, b"c", HashScheme::Plain));
+        assert!(proof.verify(tree.root(), 3, 2, b"c", HashScheme::Plain));
     }
 
     #[test]
@@ -240,33 +666,107 @@ mod tests {
             "hello 4".as_bytes(),
             "hello 5".as_bytes(),
         ];
-        let leafs = prepare_leaf_level(data);
-        let nodes_1 = prepare_node_level(leafs);
-        let nodes_2 = prepare_node_level(nodes_1);
-        let root_level = prepare_node_level(nodes_2);
-        let root = root_level.get(0).unwrap();
+        let tree = MerkleTree::from_leaves_with(&data, HashScheme::Plain);
 
         // act
-        let proofs = build_proofs(root.clone(), vec![]);
-        // {
-        //     hash: "50db240d003e4fa4832a8e5f5b38d51f260a68f6337c0c16f960c4ccfb1ac028",
-        //     index: 0,
-        //     siblings: [
-        //     "bf949020174558630551a377686f51a7cd4519be43f3514f3bdfc205ee558e6a",
-        //     "f56ad5d29ce9ca062e20bcdeec463d200ebd61169c198f500a29816b47ba97a8",
-        //     "9100cbb716df8fd40fc727edc5fe833f5846c246c8034d9ee11d5b3f2455b60a"
-        //     ]
-        // }
-        // println!("{proofs:?}");
+        let proof_0 = tree.proof(0).unwrap();
+        let proof_4 = tree.proof(4).unwrap();
 
         // assert
-        let proof_0 = &proofs[0];
+        assert_eq!(
+            hex::encode(proof_0.hash),
+            "50db240d003e4fa4832a8e5f5b38d51f260a68f6337c0c16f960c4ccfb1ac028"
+        );
+        assert_eq!(
+            hex::encode(proof_0.siblings[0].hash),
+            "bf949020174558630551a377686f51a7cd4519be43f3514f3bdfc205ee558e6a"
+        );
+        assert_eq!(proof_0.siblings.len(), 3);
+        assert!(proof_0
+            .siblings
+            .iter()
+            .all(|sibling| sibling.position == Position::Right));
+        assert!(proof_0.verify(tree.root(), 5, 0, b"hello 1", HashScheme::Plain));
+
+        // the 5th leaf is paired with itself, and its parent with a copy of itself too
+        assert_eq!(proof_4.siblings[0].hash, proof_4.hash);
+        assert_eq!(proof_4.siblings[2].position, Position::Left);
+        assert_eq!(&proof_4.derive_root(HashScheme::Plain), tree.root());
+        assert!(proof_4.verify(tree.root(), 5, 4, b"hello 5", HashScheme::Plain));
+        assert!(!proof_4.verify(tree.root(), 5, 4, b"hello 4", HashScheme::Plain));
+        assert_eq!(tree.proof(5), None);
+
+        // under RFC 6962 it moves up to the level below the root instead
+        let tree = MerkleTree::from_leaves(&data);
+        let proof_4 = tree.proof(4).unwrap();
+        assert_eq!(proof_4.siblings.len(), 1);
+        assert_eq!(proof_4.siblings[0].position, Position::Left);
+        assert!(proof_4.verify(tree.root(), 5, 4, b"hello 5", HashScheme::Rfc6962));
+    }
 
-        // TODO: implement derive_root method
-        let hash_1 = combine_and_hash(proof_0.hash.as_bytes(), proof_0.siblings[0].as_bytes());
-        let hash_2 = combine_and_hash(hash_1.as_bytes(), proof_0.siblings[1].as_bytes());
-        let hash_3 = combine_and_hash(hash_2.as_bytes(), proof_0.siblings[2].as_bytes());
+    proptest! {
+        #[test]
+        fn every_proof_verifies(
+            leaves in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..16), 1..70),
+            scheme in prop::sample::select(vec![HashScheme::Plain, HashScheme::Rfc6962]),
+        ) {
+            let tree = MerkleTree::from_leaves_with(&leaves, scheme);
+
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = tree.proof(index).unwrap();
+
+                prop_assert_eq!(proof.index, index);
+                prop_assert!(proof.verify(tree.root(), leaves.len(), index, leaf, scheme));
+            }
+            prop_assert!(tree.proof(leaves.len()).is_none());
+        }
 
-        assert_eq!(hash_3.as_bytes(), root.get_hash().as_bytes());
+        #[test]
+        fn proof_rejects_other_leaves_and_roots(
+            leaves in prop::collection::vec(any::<u32>().prop_map(u32::to_be_bytes), 2..70),
+            index in any::<prop::sample::Index>(),
+            scheme in prop::sample::select(vec![HashScheme::Plain, HashScheme::Rfc6962]),
+        ) {
+            let tree = MerkleTree::from_leaves_with(&leaves, scheme);
+            let index = index.index(leaves.len());
+            let proof = tree.proof(index).unwrap();
+
+            for leaf in &leaves {
+                prop_assert_eq!(
+                    proof.verify(tree.root(), leaves.len(), index, leaf, scheme),
+                    leaf == &leaves[index]
+                );
+            }
+
+            let mut tampered = proof.clone();
+            let last = tampered.siblings.len() - 1;
+            tampered.siblings[last].hash[0] ^= 1;
+            prop_assert!(!tampered.verify(tree.root(), leaves.len(), index, &leaves[index], scheme));
+
+            let other = MerkleTree::from_leaves_with(&leaves[1..], scheme);
+            prop_assert!(!proof.verify(other.root(), other.len(), index, &leaves[index], scheme));
+        }
+
+        #[test]
+        fn push_gives_same_tree_as_rebuild(
+            leaves in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..16), 0..70),
+            built in any::<prop::sample::Index>(),
+            scheme in prop::sample::select(vec![HashScheme::Plain, HashScheme::Rfc6962]),
+        ) {
+            let built = built.index(leaves.len() + 1);
+            let mut tree = MerkleTree::from_leaves_with(&leaves[..built], scheme);
+
+            for len in built + 1..=leaves.len() {
+                tree.push(&leaves[len - 1]);
+
+                let rebuilt = MerkleTree::from_leaves_with(&leaves[..len], scheme);
+                prop_assert_eq!(tree.root(), rebuilt.root());
+                prop_assert_eq!(tree.len(), len);
+            }
+            let rebuilt = MerkleTree::from_leaves_with(&leaves, scheme);
+            for index in 0..=leaves.len() {
+                prop_assert_eq!(tree.proof(index), rebuilt.proof(index));
+            }
+        }
     }
 }
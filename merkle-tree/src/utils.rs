@@ -4,13 +4,45 @@ use merkle_tree::Hash;
 
 use crate::merkle_tree;
 
-pub fn hash(data: &[u8]) -> Hash {
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    let hash = hasher.finalize();
-    hex::encode(hash)
+/// How leaves and nodes are hashed, and what happens to the last node of an odd level.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum HashScheme {
+    /// Leaves are `SHA-256(data)` and nodes `SHA-256(left || right)`. An odd node is paired with a
+    /// copy of itself, like Bitcoin does.
+    Plain,
+    /// Certificate Transparency (RFC 6962): leaves are `SHA-256(0x00 || data)` and nodes
+    /// `SHA-256(0x01 || left || right)`, so one can't pass for the other. An odd node moves up
+    /// a level unchanged.
+    #[default]
+    Rfc6962,
+}
+
+impl HashScheme {
+    pub fn hash_leaf(self, data: &[u8]) -> Hash {
+        match self {
+            HashScheme::Plain => hash(&[data]),
+            HashScheme::Rfc6962 => hash(&[&[0x00], data]),
+        }
+    }
+
+    pub fn hash_node(self, left: &Hash, right: &Hash) -> Hash {
+        match self {
+            HashScheme::Plain => hash(&[left, right]),
+            HashScheme::Rfc6962 => hash(&[&[0x01], left, right]),
+        }
+    }
+
+    /// Whether the last node of an odd level is paired with itself, instead of moved up.
+    pub fn pairs_odd_node(self) -> bool {
+        self == HashScheme::Plain
+    }
 }
 
-pub fn combine_and_hash(data1: &[u8], data2: &[u8]) -> Hash {
-    hash([data1, data2].concat().as_slice())
+/// SHA-256 of the concatenated `parts`.
+pub fn hash(parts: &[&[u8]]) -> Hash {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
 }